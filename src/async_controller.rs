@@ -0,0 +1,222 @@
+//! A non-blocking counterpart to `Controller` for async runtimes.
+//!
+//! `AsyncController` drives a PCA9685 over an `embedded-hal-async` I2C bus and waits
+//! on the oscillator with an async `DelayNs`, so neither bus transfers nor oscillator
+//! stabilisation block the executor. It always uses auto-increment block transfers.
+
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::{ I2c, Operation };
+
+use crate::{
+    constants,
+    channel::{
+        base::Channel,
+        errors,
+        output::Output,
+        state::ChannelState,
+    },
+    controller::{ checked_prescale_from, frequency_from, tick_length_from },
+    error::Error,
+    mode::{ Mode1, Mode2 },
+};
+
+/// A PCA9685 on an `embedded-hal-async` I2C bus.
+#[derive(Debug)]
+pub struct AsyncController<I2C, D> {
+    i2c: I2C,
+    address: u8,
+    delay: D,
+    oscillator_freq: f32,
+    prescale: u8,
+}
+
+impl<I2C: I2c, D: DelayNs> AsyncController<I2C, D> {
+
+    /// Creates an `AsyncController` for the PCA9685 at `address` on `i2c` and puts it into
+    /// a known state, see `Controller::new`. `delay` is used to wait on the oscillator.
+    /// Returns `Error::NotInitialized` if the controller does not wake up during set up.
+    pub async fn new(i2c: I2C, address: u8, delay: D) -> Result<AsyncController<I2C, D>, Error<I2C::Error>> {
+        let mut c = AsyncController{
+            i2c,
+            address,
+            delay,
+            oscillator_freq: constants::OSCILLATION_FREQ,
+            prescale: 0,
+        };
+        c.set_up().await?;
+
+        Ok(c)
+    }
+
+    /// Creates an `AsyncController` for a PCA9685 at the default slave address (`0x40`).
+    pub async fn with_default_address(i2c: I2C, delay: D) -> Result<AsyncController<I2C, D>, Error<I2C::Error>> {
+        AsyncController::new(i2c, constants::PCA9685_SLAVE_ADDRESS as u8, delay).await
+    }
+
+    /// Performs the same initial set up as `Controller`, leaving `MODE_1` at
+    /// `0x21` (ALL_CALL | AI) and `MODE_2` at `0x04` (OUTDRV).
+    async fn set_up(&mut self) -> Result<(), Error<I2C::Error>> {
+        self.write_mode2(Mode2::OUTDRV).await?;
+        self.write_mode1(Mode1::ALLCALL | Mode1::AI).await?;
+        self.delay.delay_ms(5).await;
+
+        let mode = self.read_mode1().await?;
+        self.write_mode1(mode - Mode1::SLEEP).await?;
+        self.delay.delay_ms(5).await;
+
+        if self.read_mode1().await?.contains(Mode1::SLEEP) {
+            return Err(Error::NotInitialized);
+        }
+
+        self.prescale = self.read_register(constants::PRE_SCALE).await?;
+
+        Ok(())
+    }
+
+    /// Releases the underlying I2C bus and delay.
+    pub fn release(self) -> (I2C, D) {
+        (self.i2c, self.delay)
+    }
+
+    /// Returns the slave address of the PCA9685.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Reads a single register from the controller.
+    async fn read_register(&mut self, register: u8) -> Result<u8, Error<I2C::Error>> {
+        let mut buf = [0u8];
+        self.i2c.write_read(self.address, &[register], &mut buf).await.map_err(Error::Bus)?;
+
+        Ok(buf[0])
+    }
+
+    /// Writes a single register on the controller.
+    async fn write_register(&mut self, register: u8, value: u8) -> Result<(), Error<I2C::Error>> {
+        self.i2c.write(self.address, &[register, value]).await.map_err(Error::Bus)
+    }
+
+    /// Writes `values` to consecutive registers starting at `register` in a single I2C write.
+    async fn write_registers(&mut self, register: u8, values: &[u8]) -> Result<(), Error<I2C::Error>> {
+        self.i2c.transaction(self.address, &mut [Operation::Write(&[register]), Operation::Write(values)])
+            .await
+            .map_err(Error::Bus)
+    }
+
+    /// Reads the `MODE_1` register.
+    pub async fn read_mode1(&mut self) -> Result<Mode1, Error<I2C::Error>> {
+        let bits = self.read_register(constants::MODE_1).await?;
+        Ok(Mode1::from_bits_truncate(bits))
+    }
+
+    /// Writes `mode` to the `MODE_1` register.
+    pub async fn write_mode1(&mut self, mode: Mode1) -> Result<(), Error<I2C::Error>> {
+        self.write_register(constants::MODE_1, mode.bits()).await
+    }
+
+    /// Reads the `MODE_2` register.
+    pub async fn read_mode2(&mut self) -> Result<Mode2, Error<I2C::Error>> {
+        let bits = self.read_register(constants::MODE_2).await?;
+        Ok(Mode2::from_bits_truncate(bits))
+    }
+
+    /// Writes `mode` to the `MODE_2` register.
+    pub async fn write_mode2(&mut self, mode: Mode2) -> Result<(), Error<I2C::Error>> {
+        self.write_register(constants::MODE_2, mode.bits()).await
+    }
+
+    /// Returns the oscillator frequency in Hertz that is used for prescale and pulse calculations.
+    pub fn oscillator_frequency(&self) -> f32 {
+        self.oscillator_freq
+    }
+
+    /// Overrides the oscillator frequency in Hertz used for prescale and pulse calculations.
+    pub fn set_oscillator_frequency(&mut self, oscillator_freq: f32) {
+        debug!("oscillator frequency set to {:.0}Hz", oscillator_freq);
+        self.oscillator_freq = oscillator_freq;
+    }
+
+    /// Calculates the PRE_SCALE value for `update_rate` Hertz using this controller's
+    /// oscillator frequency. Returns an error when it falls outside of `0x03..0xFF`.
+    pub fn prescale_value(&self, update_rate: f32) -> Result<u8, errors::ValueRangeError> {
+        checked_prescale_from(self.oscillator_freq, update_rate)
+    }
+
+    /// Sets the controller's output modulation rate, see `Controller::set_pwm_rate`.
+    /// The oscillator is given time to stabilise with the async delay.
+    pub async fn set_pwm_rate(&mut self, prescale_value: u8) -> Result<(), Error<I2C::Error>> {
+        let old_mode = self.read_mode1().await?;
+        let new_mode = (old_mode - Mode1::RESTART) | Mode1::SLEEP;
+
+        self.write_mode1(new_mode).await?;
+
+        debug!("setting output modulation frequency to {} (prescale)", prescale_value);
+        self.write_register(constants::PRE_SCALE, prescale_value).await?;
+        self.prescale = prescale_value;
+
+        self.write_mode1(old_mode).await?;
+        self.delay.delay_ms(5).await;
+
+        self.write_mode1(old_mode | Mode1::RESTART).await
+    }
+
+    /// Reads the PRE_SCALE register and returns the output modulation frequency
+    /// the controller is currently running at, in Hertz.
+    pub async fn pwm_rate(&mut self) -> Result<f32, Error<I2C::Error>> {
+        self.prescale = self.read_register(constants::PRE_SCALE).await?;
+
+        Ok(frequency_from(self.oscillator_freq, self.prescale))
+    }
+
+    /// Sets the controller's output modulation rate to `update_rate` Hertz.
+    /// Returns `Error::InvalidFrequency` without touching the controller
+    /// when `update_rate` is outside of the range the oscillator supports
+    /// (about 24Hz to 1526Hz with the internal 25MHz one).
+    pub async fn set_frequency(&mut self, update_rate: f32) -> Result<(), Error<I2C::Error>> {
+        let prescale_value = self.prescale_value(update_rate).map_err(Error::InvalidFrequency)?;
+        self.set_pwm_rate(prescale_value).await
+    }
+
+    /// Returns the length of a single step of the 12-bit counter in µs,
+    /// based on the last known PRE_SCALE value and the oscillator frequency.
+    pub fn tick_length(&self) -> f32 {
+        tick_length_from(self.oscillator_freq, self.prescale)
+    }
+
+    /// Sets `channel`'s registers to drive `output` in a single I2C write.
+    pub async fn set_channel<C: Channel, O: Into<Output>>(&mut self, channel: &mut C, output: O) -> Result<(), Error<I2C::Error>> {
+        let data = output.into().to_bytes();
+        self.write_registers(channel.base_address(), &data).await
+    }
+
+    /// Sets every channel to drive `output` through the `ALL_LED_*` registers.
+    pub async fn set_all_channels<O: Into<Output>>(&mut self, output: O) -> Result<(), Error<I2C::Error>> {
+        let data = output.into().to_bytes();
+        self.write_registers(constants::ALL_LED_ON_LOW, &data).await
+    }
+
+    /// Reads `channel`'s registers back from the controller and decodes them.
+    pub async fn get_channel<C: Channel>(&mut self, channel: &C) -> Result<ChannelState, Error<I2C::Error>> {
+        let mut data = [0u8; 4];
+        self.i2c.write_read(self.address, &[channel.base_address()], &mut data).await.map_err(Error::Bus)?;
+
+        Ok(ChannelState::new(channel.channel_num(), data, self.tick_length()))
+    }
+
+    /// Reads the state of all 16 channels in a single 64-byte I2C read.
+    pub async fn snapshot(&mut self) -> Result<[ChannelState; 16], Error<I2C::Error>> {
+        let mut buf = [0u8; 4 * constants::NUM_CHANNELS as usize];
+        self.i2c.write_read(self.address, &[constants::BASE_LED_ON_LOW], &mut buf).await.map_err(Error::Bus)?;
+
+        let tick_length = self.tick_length();
+        let mut states = [ChannelState::default(); 16];
+        for (i, state) in states.iter_mut().enumerate() {
+            let mut data = [0u8; 4];
+            data.copy_from_slice(&buf[4 * i..4 * i + 4]);
+            *state = ChannelState::new(i as u8, data, tick_length);
+        }
+
+        Ok(states)
+    }
+
+}
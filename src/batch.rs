@@ -0,0 +1,75 @@
+//! Channel writes that latch together.
+//!
+//! With `UpdateOn::Stop`, the default update mode, the PCA9685 only applies new
+//! channel values at the I2C STOP condition. A `Batch` collects outputs for several
+//! channels, and `Controller::write_batch` sends them in a single transaction so
+//! every channel in the batch changes on the same PWM cycle.
+
+use crate::channel::{
+    base::Channel,
+    errors,
+    output::Output,
+};
+use crate::constants;
+
+/// Outputs for up to 16 channels of a single controller, see `Controller::write_batch`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Batch {
+    outputs: [Option<Output>; constants::NUM_CHANNELS as usize],
+}
+
+impl Batch {
+
+    /// Creates an empty `Batch`.
+    pub fn new() -> Batch {
+        Batch::default()
+    }
+
+    /// Sets `channel` to drive `output` once the batch is written.
+    /// A later call for the same channel replaces the earlier output.
+    /// Returns an error if `channel` is not one of the controller's 16 channels.
+    pub fn set_channel<C: Channel, O: Into<Output>>(&mut self, channel: &C, output: O) -> Result<&mut Batch, errors::IndexRangeError> {
+        let slot = self.outputs.get_mut(channel.channel_num() as usize).ok_or(errors::IndexRangeError)?;
+        *slot = Some(output.into());
+        Ok(self)
+    }
+
+    /// Turns `channel` fully on once the batch is written.
+    pub fn set_full_on<C: Channel>(&mut self, channel: &C) -> Result<&mut Batch, errors::IndexRangeError> {
+        self.set_channel(channel, Output::FullOn)
+    }
+
+    /// Turns `channel` fully off once the batch is written.
+    pub fn set_full_off<C: Channel>(&mut self, channel: &C) -> Result<&mut Batch, errors::IndexRangeError> {
+        self.set_channel(channel, Output::FullOff)
+    }
+
+    /// Returns the output queued for channel `channel_num`, if any.
+    pub fn output(&self, channel_num: u8) -> Option<Output> {
+        self.outputs.get(channel_num as usize).copied().flatten()
+    }
+
+    /// Returns the number of channels in the batch.
+    pub fn len(&self) -> usize {
+        self.outputs.iter().filter(|output| output.is_some()).count()
+    }
+
+    /// Returns whether the batch holds no channels.
+    pub fn is_empty(&self) -> bool {
+        self.outputs.iter().all(|output| output.is_none())
+    }
+
+    /// Removes every channel from the batch.
+    pub fn clear(&mut self) {
+        self.outputs = [None; constants::NUM_CHANNELS as usize];
+    }
+
+    /// Returns the lowest and highest channel numbers in the batch.
+    pub(crate) fn span(&self) -> Option<(u8, u8)> {
+        let first = self.outputs.iter().position(|output| output.is_some())?;
+        let last = self.outputs.iter().rposition(|output| output.is_some())?;
+
+        Some((first as u8, last as u8))
+    }
+
+}
@@ -0,0 +1,34 @@
+#[macro_use] extern crate quicli;
+extern crate rust_pca9685;
+
+use std::path::PathBuf;
+
+use quicli::prelude::*;
+use rust_pca9685::discover::discover;
+
+#[derive(Debug, StructOpt)]
+struct Cli {
+    #[structopt(long = "i2c-device", short = "d", default_value = "/dev/i2c-0", help = "I2C device node", parse(from_os_str))]
+    device: PathBuf,
+
+    #[structopt(flatten)]
+    verbosity: Verbosity,
+}
+
+main!(|args: Cli, log_level: verbosity| {
+    let candidates = discover(args.device.as_path())?;
+    if candidates.is_empty() {
+        println!("no PCA9685 found on {}", args.device.display());
+    }
+
+    for candidate in candidates {
+        println!(
+            "{:#04x}: MODE_1 {:#04x}, MODE_2 {:#04x}, PRE_SCALE {:#04x}{}",
+            candidate.address(),
+            candidate.mode1().bits(),
+            candidate.mode2().bits(),
+            candidate.prescale(),
+            if candidate.at_reset_defaults() { " (power-on defaults)" } else { "" },
+        );
+    }
+});
@@ -6,10 +6,10 @@ use rust_pca9685::channel::servo::{ ServoChannel, ServoSettings };
 
 #[derive(Debug, StructOpt)]
 struct Cli {
-    #[structopt(long = "servo-min", short = "m", default_value = "0", help = "Servo pulse minimum value")]
+    #[structopt(long = "servo-min", short = "m", default_value = "500", help = "Servo pulse minimum (µs)")]
     servo_min: u16,
 
-    #[structopt(long = "servo-max", short = "x", default_value = "4095", help = "Servo pulse maximum value")]
+    #[structopt(long = "servo-max", short = "x", default_value = "2500", help = "Servo pulse maximum (µs)")]
     servo_max: u16,
 
     #[structopt(help = "Pulse length in µs")]
@@ -1,4 +1,5 @@
 extern crate ctrlc;
+extern crate embedded_hal;
 extern crate i2cdev;
 #[macro_use] extern crate quicli;
 extern crate rust_pca9685;
@@ -8,18 +9,18 @@ use std::path::PathBuf;
 use std::sync::atomic::{ AtomicBool, Ordering };
 use std::sync::Arc;
 
-use i2cdev::core::*;
 #[cfg(target_os = "linux")]
 use i2cdev::linux::LinuxI2CDevice;
+use embedded_hal::delay::DelayNs;
 use i2cdev::mock::MockI2CDevice;
 use quicli::prelude::*;
 use rust_pca9685::{
-    channel::servo::{ ServoChannel, ServoSettings },
+    chain::ControllerChain,
+    channel::servo::ServoSettings,
     constants,
-    controller::{
-        calculate_prescale_value,
-        Controller,
-    },
+    controller::Controller,
+    device::Device,
+    Error,
 };
 
 fn parse_hex(src: &str) -> std::result::Result<u16, ParseIntError> {
@@ -39,8 +40,8 @@ struct Cli {
     #[structopt(long = "i2c-device", short = "d", default_value = "/dev/i2c-0", help = "I2C device node", parse(from_os_str))]
     device: PathBuf,
 
-    #[structopt(long = "i2c-slave-addr", short = "s", help = "I2C slave address (in hex) for PCA9685", parse(try_from_str = "parse_hex"))]
-    slave_address: Option<u16>,
+    #[structopt(long = "i2c-slave-addr", short = "s", help = "I2C slave address (in hex) for PCA9685, repeat for chained boards", parse(try_from_str = "parse_hex"))]
+    slave_addresses: Vec<u16>,
 
     #[structopt(long = "i2c-mock", short = "M", help = "Use the MockI2CDevice instead of LinuxI2CDevice")]
     mock_device: bool,
@@ -48,13 +49,13 @@ struct Cli {
     #[structopt(long = "pwm-rate", short = "p", default_value = "60", help = "PWM controller oscillation rate")]
     pwm_rate: f32,
 
-    #[structopt(long = "channel", short = "c", default_value = "0", help = "Servo channel to sweep")]
-    channel: u8,
+    #[structopt(long = "channel", short = "c", default_value = "0", help = "Servo channel to sweep, numbered across chained boards")]
+    channel: usize,
 
-    #[structopt(long = "servo-min", short = "m", default_value = "0", help = "Servo pulse minimum value")]
+    #[structopt(long = "servo-min", short = "m", default_value = "500", help = "Servo pulse minimum (µs)")]
     servo_min: u16,
 
-    #[structopt(long = "servo-max", short = "x", default_value = "4095", help = "Servo pulse maximum value")]
+    #[structopt(long = "servo-max", short = "x", default_value = "2500", help = "Servo pulse maximum (µs)")]
     servo_max: u16,
 
     #[structopt(long = "step-size", short = "z", default_value = "1.0", help = "Angle step size", parse(try_from_str = "parse_positive_float"))]
@@ -68,29 +69,39 @@ struct Cli {
 }
 
 main!(|args: Cli, log_level: verbosity| {
-    let slave_address = args.slave_address.unwrap_or(constants::PCA9685_SLAVE_ADDRESS);
-    warn!("using {:#0.2x} as slave address", slave_address);
+    let mut slave_addresses = args.slave_addresses.clone();
+    if slave_addresses.is_empty() {
+        slave_addresses.push(constants::PCA9685_SLAVE_ADDRESS);
+    }
 
     if args.mock_device {
         info!("using mock device");
 
-        let mut device = MockI2CDevice::new();
-        let mut controller = Controller::new(&mut device);
-        run_sweep(args, &mut controller);
+        let mut chain = ControllerChain::new();
+        for slave_address in &slave_addresses {
+            warn!("using {:#0.2x} as slave address", slave_address);
+            chain.add_board(*slave_address, Controller::new(MockI2CDevice::new())?)?;
+        }
+        run_sweep(args, &mut chain)?;
     } else {
         warn!("using real i2c device!");
 
-        #[cfg(target_os = "linux")]
-        let mut device = LinuxI2CDevice::new(args.device.as_path(), slave_address);
-        #[cfg(not(target_os = "linux"))]
-        let mut device = MockI2CDevice::new();
+        let mut chain = ControllerChain::new();
+        for slave_address in &slave_addresses {
+            warn!("using {:#0.2x} as slave address", slave_address);
+
+            #[cfg(target_os = "linux")]
+            let device = LinuxI2CDevice::new(args.device.as_path(), *slave_address)?;
+            #[cfg(not(target_os = "linux"))]
+            let device = MockI2CDevice::new();
 
-        let mut controller = Controller::new(&mut device);
-        run_sweep(args, &mut controller);
+            chain.add_board(*slave_address, Controller::new(device)?)?;
+        }
+        run_sweep(args, &mut chain)?;
     }
 });
 
-fn run_sweep<'a, T: I2CDevice + 'a>(args: Cli, controller: &mut Controller<T>) {
+fn run_sweep<T: Device, D: DelayNs>(args: Cli, chain: &mut ControllerChain<T, D>) -> std::result::Result<(), Error<T::Error>> {
     // Set up a keyboard interrupt handler
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
@@ -99,17 +110,13 @@ fn run_sweep<'a, T: I2CDevice + 'a>(args: Cli, controller: &mut Controller<T>) {
         r.store(false, Ordering::SeqCst);
     }).expect("Could not set ctrlc handler!");
 
-    let pwm_rate = calculate_prescale_value(args.pwm_rate);
-    controller.set_pwm_rate(pwm_rate).unwrap();
+    chain.set_frequency(args.pwm_rate)?;
 
-    let mut channel = ServoChannel::new_with_settings(
-        args.channel,
-        ServoSettings::new(args.servo_min, args.servo_max),
-    ).unwrap();
+    let settings = ServoSettings::new(args.servo_min, args.servo_max);
 
     while running.load(Ordering::SeqCst) {
-        sweep_from(controller, &mut channel, -90.0, 90.0, args.step_size);
-        sweep_from(controller, &mut channel, 90.0, -90.0, -args.step_size);
+        sweep_from(chain, args.channel, settings, -90.0, 90.0, args.step_size)?;
+        sweep_from(chain, args.channel, settings, 90.0, -90.0, -args.step_size)?;
 
         if !args.continuous {
             break;
@@ -117,16 +124,16 @@ fn run_sweep<'a, T: I2CDevice + 'a>(args: Cli, controller: &mut Controller<T>) {
     }
 
     // Reorient the servo into a neutral position
-    let neutral = args.servo_max - ((args.servo_max - args.servo_min) / 2);
-    controller.set_channel(&mut channel, 0, neutral).unwrap();
+    chain.set_servo_angle(args.channel, settings, 0.0)
 }
 
-fn sweep_from<'a, T: I2CDevice + 'a>(controller: &mut Controller<T>, channel: &mut ServoChannel, start: f32, end: f32, step: f32) {
+fn sweep_from<T: Device, D: DelayNs>(chain: &mut ControllerChain<T, D>, channel: usize, settings: ServoSettings, start: f32, end: f32, step: f32) -> std::result::Result<(), Error<T::Error>> {
     let mut position = start;
     while (start < end && position < end) || (start > end && position > end) {
-        let pulse = channel.degrees_to_pulse_time(position).unwrap();
-        controller.set_channel(channel, 0, pulse).unwrap();
+        chain.set_servo_angle(channel, settings, position)?;
 
-        position = position + step;
+        position += step;
     }
-}
\ No newline at end of file
+
+    Ok(())
+}
@@ -0,0 +1,294 @@
+//! Driving several PCA9685 boards as one bank of channels.
+//!
+//! Each PCA9685 answers on its own I2C address, so boards can be chained to get
+//! more than 16 outputs. A `ControllerChain` keeps one `Board` per address and
+//! addresses channels either as a `(board, channel)` pair or as a flat index,
+//! where channel 0 of the second board is index 16.
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{
+    constants,
+    channel::{
+        errors,
+        led::LedChannel,
+        output::Output,
+        phase::PhaseOffset,
+        servo::ServoChannel,
+        servo::ServoSettings,
+        state::ChannelState,
+    },
+    controller::Controller,
+    device::Device,
+    error::Error,
+    rgb::ColorLed,
+};
+
+/// A channel in a `ControllerChain`, given as the board's position in the chain
+/// and the channel index on that board.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChainChannel {
+    board: usize,
+    channel: u8,
+}
+
+impl ChainChannel {
+
+    /// Creates a `ChainChannel` for `channel` on the board at position `board`.
+    pub fn new(board: usize, channel: u8) -> ChainChannel {
+        ChainChannel{
+            board,
+            channel,
+        }
+    }
+
+    /// Returns the position of the board in the chain.
+    pub fn board(&self) -> usize {
+        self.board
+    }
+
+    /// Returns the channel index on the board.
+    pub fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    /// Returns the flat index of this channel across the chain.
+    pub fn flat_index(&self) -> usize {
+        self.board * constants::NUM_CHANNELS as usize + self.channel as usize
+    }
+
+}
+
+impl From<usize> for ChainChannel {
+
+    /// Splits a flat index into a board position and channel index.
+    fn from(index: usize) -> ChainChannel {
+        let per_board = constants::NUM_CHANNELS as usize;
+        ChainChannel::new(index / per_board, (index % per_board) as u8)
+    }
+
+}
+
+impl From<(usize, u8)> for ChainChannel {
+
+    fn from((board, channel): (usize, u8)) -> ChainChannel {
+        ChainChannel::new(board, channel)
+    }
+
+}
+
+/// A single PCA9685 in a `ControllerChain`, identified by its I2C slave address.
+#[derive(Debug)]
+pub struct Board<T: Device, D: DelayNs> {
+    address: u16,
+    controller: Controller<T, D>,
+}
+
+impl<T: Device, D: DelayNs> Board<T, D> {
+
+    /// Creates a `Board` for `controller`, which drives the PCA9685 at `address`.
+    pub fn new(address: u16, controller: Controller<T, D>) -> Board<T, D> {
+        Board{
+            address,
+            controller,
+        }
+    }
+
+    /// Returns the I2C slave address of the board.
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// Returns the board's controller.
+    pub fn controller(&self) -> &Controller<T, D> {
+        &self.controller
+    }
+
+    /// Returns the board's controller for direct access.
+    pub fn controller_mut(&mut self) -> &mut Controller<T, D> {
+        &mut self.controller
+    }
+
+    /// Consumes the `Board` and returns its controller.
+    pub fn into_inner(self) -> Controller<T, D> {
+        self.controller
+    }
+
+}
+
+/// Several PCA9685 boards driven as one bank of `16 * len()` channels.
+#[derive(Debug)]
+pub struct ControllerChain<T: Device, D: DelayNs> {
+    boards: Vec<Board<T, D>>,
+}
+
+impl<T: Device, D: DelayNs> Default for ControllerChain<T, D> {
+
+    fn default() -> ControllerChain<T, D> {
+        ControllerChain::new()
+    }
+
+}
+
+impl<T: Device, D: DelayNs> ControllerChain<T, D> {
+
+    /// Creates an empty `ControllerChain`.
+    pub fn new() -> ControllerChain<T, D> {
+        ControllerChain{
+            boards: Vec::new(),
+        }
+    }
+
+    /// Appends `controller`, which drives the PCA9685 at `address`, to the end of the chain.
+    /// Returns the position of the new board, or `Error::DuplicateAddress` if a board
+    /// with the same address is already part of the chain.
+    pub fn add_board(&mut self, address: u16, controller: Controller<T, D>) -> Result<usize, Error<T::Error>> {
+        if self.position(address).is_some() {
+            return Err(Error::DuplicateAddress(address));
+        }
+
+        debug!("adding board {:#04x} at position {}", address, self.boards.len());
+        self.boards.push(Board::new(address, controller));
+
+        Ok(self.boards.len() - 1)
+    }
+
+    /// Returns the number of boards in the chain.
+    pub fn len(&self) -> usize {
+        self.boards.len()
+    }
+
+    /// Returns whether the chain has no boards.
+    pub fn is_empty(&self) -> bool {
+        self.boards.is_empty()
+    }
+
+    /// Returns the number of channels across all boards.
+    pub fn num_channels(&self) -> usize {
+        self.boards.len() * constants::NUM_CHANNELS as usize
+    }
+
+    /// Returns the boards in chain order.
+    pub fn boards(&self) -> &[Board<T, D>] {
+        &self.boards
+    }
+
+    /// Returns the board at position `board`.
+    pub fn board(&self, board: usize) -> Option<&Board<T, D>> {
+        self.boards.get(board)
+    }
+
+    /// Returns the board at position `board` for direct access.
+    pub fn board_mut(&mut self, board: usize) -> Option<&mut Board<T, D>> {
+        self.boards.get_mut(board)
+    }
+
+    /// Returns the position of the board at I2C slave address `address`.
+    pub fn position(&self, address: u16) -> Option<usize> {
+        self.boards.iter().position(|board| board.address == address)
+    }
+
+    /// Consumes the chain and returns its boards.
+    pub fn into_boards(self) -> Vec<Board<T, D>> {
+        self.boards
+    }
+
+    /// Sets the output modulation rate of every board to `update_rate` Hertz.
+    /// Returns `Error::InvalidFrequency` without touching any board if
+    /// `update_rate` is out of range for one of them.
+    pub fn set_frequency(&mut self, update_rate: f32) -> Result<(), Error<T::Error>> {
+        let mut prescales = Vec::with_capacity(self.boards.len());
+        for board in &self.boards {
+            prescales.push(board.controller.prescale_value(update_rate).map_err(Error::InvalidFrequency)?);
+        }
+
+        for (board, prescale) in self.boards.iter_mut().zip(prescales) {
+            board.controller.set_pwm_rate(prescale)?;
+        }
+
+        Ok(())
+    }
+
+    /// Uses `phase_offset` on every board, see `Controller::set_phase_offset`.
+    pub fn set_phase_offset(&mut self, phase_offset: PhaseOffset) {
+        for board in &mut self.boards {
+            board.controller.set_phase_offset(phase_offset);
+        }
+    }
+
+    /// Sets the channel at `index` to drive `output`. `index` is either a flat
+    /// channel index or a `(board, channel)` pair.
+    pub fn set_channel<I: Into<ChainChannel>, O: Into<Output>>(&mut self, index: I, output: O) -> Result<(), Error<T::Error>> {
+        let (board, channel_num) = self.locate(index.into())?;
+        let controller = &mut self.boards[board].controller;
+        let mut channel = LedChannel::new(channel_num)?;
+        controller.set_channel(&mut channel, output)
+    }
+
+    /// Sets the channel at `index` to output a pulse of `pulse_width` µs.
+    pub fn set_pulse_width<I: Into<ChainChannel>>(&mut self, index: I, pulse_width: u16) -> Result<(), Error<T::Error>> {
+        let (board, channel_num) = self.locate(index.into())?;
+        let controller = &mut self.boards[board].controller;
+        let mut channel = LedChannel::new(channel_num)?;
+        controller.set_pulse_width(&mut channel, pulse_width)
+    }
+
+    /// Moves the servo at `index`, which moves through its range as described by
+    /// `settings`, to `angle` degrees.
+    pub fn set_servo_angle<I: Into<ChainChannel>>(&mut self, index: I, settings: ServoSettings, angle: f32) -> Result<(), Error<T::Error>> {
+        let (board, channel_num) = self.locate(index.into())?;
+        let controller = &mut self.boards[board].controller;
+        let mut channel = ServoChannel::new_with_settings(channel_num, settings)?;
+        controller.set_servo_angle(&mut channel, angle)
+    }
+
+    /// Shows `color` on the colour LED `led`, whose components may sit on different boards.
+    /// The components on each board are written in a single I2C transaction.
+    /// Returns `Error::IndexRange` without writing anything if a component's board is not in the chain.
+    pub fn set_color<L: ColorLed, C: Into<L::Color>>(&mut self, led: &L, color: C) -> Result<(), Error<T::Error>> {
+        let outputs = led.outputs(color.into())?;
+        let outputs = outputs.as_slice();
+        for output in outputs {
+            self.locate(ChainChannel::new(output.board, output.channel_num))?;
+        }
+
+        for (i, output) in outputs.iter().enumerate() {
+            // Each board is written once, when its first component comes up
+            if outputs[..i].iter().any(|earlier| earlier.board == output.board) {
+                continue;
+            }
+
+            let on_board = outputs.iter().filter(|other| other.board == output.board);
+            self.boards[output.board].controller.write_components(on_board)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the channel at `index` back from its board.
+    pub fn get_channel<I: Into<ChainChannel>>(&mut self, index: I) -> Result<ChannelState, Error<T::Error>> {
+        let (board, channel_num) = self.locate(index.into())?;
+        let controller = &mut self.boards[board].controller;
+        controller.get_channel(&LedChannel::new(channel_num)?)
+    }
+
+    /// Sets every channel on every board to drive `output`.
+    pub fn set_all_channels<O: Into<Output>>(&mut self, output: O) -> Result<(), Error<T::Error>> {
+        let output = output.into();
+        for board in &mut self.boards {
+            board.controller.set_all_channels(output)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks `index` against the chain and returns its board position and on-board channel index.
+    fn locate(&self, index: ChainChannel) -> Result<(usize, u8), Error<T::Error>> {
+        if index.board >= self.boards.len() || index.channel >= constants::NUM_CHANNELS as u8 {
+            return Err(Error::IndexRange(errors::IndexRangeError::new()));
+        }
+
+        Ok((index.board, index.channel))
+    }
+
+}
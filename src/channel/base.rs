@@ -1,16 +1,11 @@
-use i2cdev::core::*;
 
-use ::{
+use crate::{
     constants,
-    channel::errors,
+    channel::output::Output,
+    device::Device,
+    error::Error,
 };
 
-#[derive(Debug)]
-pub enum ChannelError {
-    IndexRange(errors::IndexRangeError),
-    ValueRange(errors::ValueRangeError),
-}
-
 pub trait Channel {
     /// Returns the channel index for the `Channel` impl.
     fn channel_num(&self) -> u8;
@@ -60,49 +55,46 @@ pub trait Channel {
     /// Reads the values for a LED channel's registers.
     /// Will read one `u8` value from `on_addrs` and `off` addrs and return
     /// a slice of the value.
-    fn read_channel<'a, T: I2CDevice + 'a>(&self, dev: &'a mut T) -> Result<[u8; 4], T::Error> {
-        let mut results = Vec::with_capacity(4);
-
-        results.push(dev.smbus_read_byte_data(self.on_low()));
-        results.push(dev.smbus_read_byte_data(self.on_high()));
-        results.push(dev.smbus_read_byte_data(self.off_low()));
-        results.push(dev.smbus_read_byte_data(self.off_high()));
-
-        let mut bytes = Vec::with_capacity(4);
-        for result in results {
-            if result.is_err() {
-                return Err(result.unwrap_err());
-            }
+    fn read_channel<'a, T: Device + 'a>(&self, dev: &'a mut T) -> Result<[u8; 4], Error<T::Error>> {
+        Ok([
+            dev.read_register(self.on_low()).map_err(Error::Bus)?,
+            dev.read_register(self.on_high()).map_err(Error::Bus)?,
+            dev.read_register(self.off_low()).map_err(Error::Bus)?,
+            dev.read_register(self.off_high()).map_err(Error::Bus)?,
+        ])
+    }
 
-            bytes.push(result.unwrap());
-        }
+    /// Reads a channel's registers and decodes them into an `Output`,
+    /// taking the full on and full off bits into account.
+    fn read_output<'a, T: Device + 'a>(&self, dev: &'a mut T) -> Result<Output, Error<T::Error>> {
+        Ok(Output::from_bytes(self.read_channel(dev)?))
+    }
 
+    /// Reads the values for a LED channel's registers in a single I2C read.
+    /// Requires auto-increment (`AI` on `MODE_1`) to be enabled on the controller.
+    fn read_channel_block<'a, T: Device + 'a>(&self, dev: &'a mut T) -> Result<[u8; 4], Error<T::Error>> {
         let mut channel_values: [u8; 4] = [0, 0, 0, 0];
-        for i in 0..4 {
-            channel_values[i] = bytes[i];
-        }
+
+        dev.read_registers(self.base_address(), &mut channel_values).map_err(Error::Bus)?;
 
         Ok(channel_values)
     }
 
     /// Writes the values in `data` into a channel's registers.
-    fn write_channel<'a, T: I2CDevice + 'a>(&self, dev: &'a mut T, data: [u8; 4]) -> Result<(), T::Error> {
-        let mut results = Vec::with_capacity(4);
-
-        let reg_addrs = vec![self.on_low(), self.on_high(), self.off_low(), self.off_high()];
-        let write_to = reg_addrs.into_iter().zip(data.into_iter());
-
-        for (reg, byte) in write_to.into_iter() {
-            results.push(dev.smbus_write_byte_data(reg, *byte));
-        }
+    fn write_channel<'a, T: Device + 'a>(&self, dev: &'a mut T, data: [u8; 4]) -> Result<(), Error<T::Error>> {
+        let reg_addrs = [self.on_low(), self.on_high(), self.off_low(), self.off_high()];
 
-        for result in results {
-            if result.is_err() {
-                return Err(result.unwrap_err());
-            }
+        for (reg, byte) in reg_addrs.iter().zip(data.iter()) {
+            dev.write_register(*reg, *byte).map_err(Error::Bus)?;
         }
 
         Ok(())
     }
 
+    /// Writes the values in `data` into a channel's registers in a single I2C write.
+    /// Requires auto-increment (`AI` on `MODE_1`) to be enabled on the controller.
+    fn write_channel_block<'a, T: Device + 'a>(&self, dev: &'a mut T, data: [u8; 4]) -> Result<(), Error<T::Error>> {
+        dev.write_registers(self.base_address(), &data).map_err(Error::Bus)
+    }
+
 }
\ No newline at end of file
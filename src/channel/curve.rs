@@ -0,0 +1,172 @@
+//! Brightness curves for LED channels.
+//!
+//! The eye's response to light is far from linear, so an LED driven with a linear
+//! duty cycle seems to jump from dark to full brightness at the bottom of its range.
+//! A `BrightnessCurve` maps a perceived brightness (`0.0..1.0`) onto the duty cycle
+//! that produces it, and back again when a channel is read.
+
+use libm;
+
+use crate::channel::errors;
+use crate::constants;
+
+/// Mapping from perceived brightness to duty cycle, both as fractions (`0.0..1.0`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BrightnessCurve {
+    /// The duty cycle is the brightness.
+    #[default]
+    Linear,
+    /// The duty cycle is the brightness raised to the given exponent, commonly `2.2` to `2.8`.
+    /// The exponent must be positive, see `BrightnessCurve::gamma`.
+    Gamma(f32),
+    /// The CIE 1931 lightness function, treating brightness as L* / 100.
+    Cie1931,
+    /// A lookup table of on ticks (`0..4096`) for evenly spaced brightness values, with
+    /// the first entry at `0.0` and the last at `1.0`. Values in between are interpolated.
+    /// The table must be non-decreasing and hold at least two entries, see `BrightnessCurve::lut`.
+    Lut(&'static [u16]),
+}
+
+impl BrightnessCurve {
+
+    /// Creates a `BrightnessCurve::Gamma`, returning an error unless `exponent` is
+    /// positive and finite.
+    ///
+    /// ```
+    /// # extern crate rust_pca9685;
+    /// # use rust_pca9685::channel::curve::BrightnessCurve;
+    /// #
+    /// assert_eq!(BrightnessCurve::Gamma(2.2), BrightnessCurve::gamma(2.2).unwrap());
+    /// assert!(BrightnessCurve::gamma(0.0).is_err());
+    /// ```
+    pub fn gamma(exponent: f32) -> Result<BrightnessCurve, errors::ValueRangeError> {
+        if !(exponent > 0.0 && exponent.is_finite()) {
+            return Err(errors::ValueRangeError::new(
+                errors::Value::Float(0.0),
+                errors::Value::Float(f32::INFINITY),
+                errors::Value::Float(exponent),
+            ));
+        }
+
+        Ok(BrightnessCurve::Gamma(exponent))
+    }
+
+    /// Creates a `BrightnessCurve::Lut`, returning an error if `table` holds fewer than
+    /// two entries, decreases anywhere or has an entry above `4096`.
+    ///
+    /// ```
+    /// # extern crate rust_pca9685;
+    /// # use rust_pca9685::channel::curve::BrightnessCurve;
+    /// #
+    /// static TABLE: [u16; 3] = [0, 512, 4096];
+    /// assert_eq!(BrightnessCurve::Lut(&TABLE), BrightnessCurve::lut(&TABLE).unwrap());
+    /// assert!(BrightnessCurve::lut(&[0, 2048, 1024]).is_err());
+    /// assert!(BrightnessCurve::lut(&[4096]).is_err());
+    /// ```
+    pub fn lut(table: &'static [u16]) -> Result<BrightnessCurve, errors::ValueRangeError> {
+        if table.len() < 2 {
+            return Err(errors::ValueRangeError::new(
+                errors::Value::Int(2),
+                errors::Value::Float(f32::INFINITY),
+                errors::Value::Int(table.len() as i32),
+            ));
+        }
+
+        let mut previous = 0;
+        for &ticks in table {
+            if ticks < previous || ticks > constants::STEP_SIZE as u16 {
+                return Err(errors::ValueRangeError::new(
+                    errors::Value::Int(previous as i32),
+                    errors::Value::Float(constants::STEP_SIZE),
+                    errors::Value::Int(ticks as i32),
+                ));
+            }
+            previous = ticks;
+        }
+
+        Ok(BrightnessCurve::Lut(table))
+    }
+
+    /// Maps `brightness` onto the duty cycle that produces it.
+    ///
+    /// ```
+    /// # extern crate rust_pca9685;
+    /// # use rust_pca9685::channel::curve::BrightnessCurve;
+    /// #
+    /// assert_eq!(0.25, BrightnessCurve::Gamma(2.0).apply(0.5));
+    /// assert!((BrightnessCurve::Cie1931.apply(0.5) - 0.184).abs() < 0.001);
+    /// ```
+    pub fn apply(&self, brightness: f32) -> f32 {
+        let brightness = brightness.clamp(0.0, 1.0);
+
+        match *self {
+            BrightnessCurve::Linear => brightness,
+            BrightnessCurve::Gamma(exponent) => libm::powf(brightness, exponent),
+            BrightnessCurve::Cie1931 => {
+                let lightness = brightness * 100.0;
+                if lightness <= 8.0 {
+                    lightness / 903.3
+                } else {
+                    libm::powf((lightness + 16.0) / 116.0, 3.0)
+                }
+            },
+            BrightnessCurve::Lut(table) => lut_apply(table, brightness),
+        }
+    }
+
+    /// Maps `duty_cycle` back onto the brightness it produces, the inverse of `apply`.
+    pub fn invert(&self, duty_cycle: f32) -> f32 {
+        let duty_cycle = duty_cycle.clamp(0.0, 1.0);
+
+        match *self {
+            BrightnessCurve::Linear => duty_cycle,
+            BrightnessCurve::Gamma(exponent) => libm::powf(duty_cycle, 1.0 / exponent),
+            BrightnessCurve::Cie1931 => {
+                if duty_cycle <= 8.0 / 903.3 {
+                    duty_cycle * 903.3 / 100.0
+                } else {
+                    (116.0 * libm::cbrtf(duty_cycle) - 16.0) / 100.0
+                }
+            },
+            BrightnessCurve::Lut(table) => lut_invert(table, duty_cycle),
+        }
+    }
+
+}
+
+/// Interpolates the on ticks for `brightness` from `table` and returns them as a duty cycle.
+fn lut_apply(table: &[u16], brightness: f32) -> f32 {
+    if table.len() < 2 {
+        return brightness;
+    }
+
+    let position = brightness * (table.len() - 1) as f32;
+    let index = (position as usize).min(table.len() - 2);
+    let weight = position - index as f32;
+
+    let ticks = table[index] as f32 + (table[index + 1] as f32 - table[index] as f32) * weight;
+    ticks / constants::STEP_SIZE
+}
+
+/// Finds the brightness in `table` whose interpolated on ticks match `duty_cycle`.
+fn lut_invert(table: &[u16], duty_cycle: f32) -> f32 {
+    if table.len() < 2 {
+        return duty_cycle;
+    }
+
+    let ticks = duty_cycle * constants::STEP_SIZE;
+    if ticks <= table[0] as f32 {
+        return 0.0;
+    }
+
+    let last = table.len() - 1;
+    for index in 0..last {
+        let (low, high) = (table[index] as f32, table[index + 1] as f32);
+        if ticks <= high {
+            let weight = if high > low { (ticks - low) / (high - low) } else { 0.0 };
+            return (index as f32 + weight) / last as f32;
+        }
+    }
+
+    1.0
+}
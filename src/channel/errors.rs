@@ -1,7 +1,8 @@
+use core::fmt;
+#[cfg(feature = "std")]
 use std::error;
-use std::fmt;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct IndexRangeError;
 
 impl IndexRangeError {
@@ -12,6 +13,7 @@ impl IndexRangeError {
 
 }
 
+#[cfg(feature = "std")]
 impl error::Error for IndexRangeError { }
 
 impl fmt::Display for IndexRangeError {
@@ -50,14 +52,15 @@ impl ValueRangeError {
 
     pub fn new(min: Value, max: Value, val: Value) -> ValueRangeError {
         ValueRangeError{
-            min: min,
-            max: max,
-            val: val,
+            min,
+            max,
+            val,
         }
     }
 
 }
 
+#[cfg(feature = "std")]
 impl error::Error for ValueRangeError { }
 
 impl fmt::Display for ValueRangeError {
@@ -1,18 +1,66 @@
-use std::fmt;
+use core::fmt;
 
-use ::channel::{
+use libm;
+
+use crate::channel::{
     base::Channel,
+    curve::BrightnessCurve,
     errors,
 };
+use crate::constants;
+
+/// Brightness of an LED, in one of the scales callers commonly work in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Brightness {
+    /// A fraction of full brightness, `0.0..1.0`.
+    Fraction(f32),
+    /// A percentage of full brightness, `0.0..100.0`.
+    Percent(f32),
+    /// A 16-bit level, where `0xffff` is full brightness.
+    Level(u16),
+}
+
+impl Brightness {
+
+    /// Returns the brightness as a fraction, validating it against the range of its scale.
+    pub fn fraction(self) -> Result<f32, errors::ValueRangeError> {
+        let (value, max) = match self {
+            Brightness::Fraction(value) => (value, 1.0),
+            Brightness::Percent(value) => (value, 100.0),
+            Brightness::Level(value) => return Ok(value as f32 / u16::MAX as f32),
+        };
+
+        if !(0.0..=max).contains(&value) {
+            return Err(errors::ValueRangeError::new(
+                errors::Value::Float(0.0),
+                errors::Value::Float(max),
+                errors::Value::Float(value),
+            ));
+        }
+
+        Ok(value / max)
+    }
+
+}
+
+impl From<f32> for Brightness {
+
+    /// Creates a `Brightness::Fraction`.
+    fn from(fraction: f32) -> Brightness {
+        Brightness::Fraction(fraction)
+    }
+
+}
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct LedChannel {
     channel_num: u8,
+    curve: BrightnessCurve,
 }
 
 impl LedChannel {
 
-    /// Creates a `LedChannel` at the specified channel register.
+    /// Creates a `LedChannel` at the specified channel register, with a linear brightness curve.
     pub fn new(channel_num: u8) -> Result<LedChannel, errors::IndexRangeError> {
         if channel_num > 15 {
             return Err(errors::IndexRangeError);
@@ -20,18 +68,60 @@ impl LedChannel {
 
         Ok(
             LedChannel{
-                channel_num: channel_num,
+                channel_num,
+                curve: BrightnessCurve::Linear,
             }
         )
     }
 
+    /// Creates a `LedChannel` at the specified channel register that maps brightness through `curve`.
+    pub fn new_with_curve(channel_num: u8, curve: BrightnessCurve) -> Result<LedChannel, errors::IndexRangeError> {
+        let mut chan = LedChannel::new(channel_num)?;
+        chan.curve = curve;
+        Ok(chan)
+    }
+
+    /// Returns the brightness curve of this channel.
+    pub fn curve(&self) -> BrightnessCurve {
+        self.curve
+    }
+
+    /// Maps brightness through `curve` from now on.
+    pub fn set_curve(&mut self, curve: BrightnessCurve) {
+        self.curve = curve;
+    }
+
+    /// Converts `brightness` into the number of counter steps per period the LED is on,
+    /// passing it through the channel's brightness curve. `0` means full off and `4096`
+    /// full on, which the controller drives with the dedicated full off and full on bits.
+    ///
+    /// ```
+    /// # extern crate rust_pca9685;
+    /// # use rust_pca9685::channel::led::{ Brightness, LedChannel };
+    /// #
+    /// let led = LedChannel::new(0).unwrap();
+    /// assert_eq!(1024, led.brightness_to_ticks(Brightness::Percent(25.0)).unwrap());
+    /// assert_eq!(4096, led.brightness_to_ticks(Brightness::Level(0xffff)).unwrap());
+    /// assert!(led.brightness_to_ticks(1.5).is_err());
+    /// ```
+    pub fn brightness_to_ticks<B: Into<Brightness>>(&self, brightness: B) -> Result<u16, errors::ValueRangeError> {
+        let duty_cycle = self.curve.apply(brightness.into().fraction()?);
+        Ok(libm::roundf(duty_cycle * constants::STEP_SIZE) as u16)
+    }
+
+    /// Converts the number of counter steps per period the LED is on back into
+    /// a fraction of full brightness, inverting the channel's brightness curve.
+    pub fn ticks_to_brightness(&self, ticks: u16) -> f32 {
+        self.curve.invert(ticks as f32 / constants::STEP_SIZE)
+    }
+
 }
 
 impl Channel for LedChannel {
 
     /// Returns the channel index for this `ServoChannel`
     fn channel_num(&self) -> u8 {
-        return self.channel_num;
+        self.channel_num
     }
 
 }
@@ -4,6 +4,12 @@
 //! `ServoChannel` and `LedChannel` impls.
 
 pub mod base;
+pub mod curve;
 pub mod errors;
 pub mod led;
+pub mod output;
+pub mod phase;
+#[cfg(feature = "std")]
+pub mod registry;
 pub mod servo;
+pub mod state;
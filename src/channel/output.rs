@@ -0,0 +1,71 @@
+use crate::constants;
+
+/// The state a channel's output is driven to.
+///
+/// Besides a regular PWM signal, every channel has dedicated "full on" and
+/// "full off" bits (bit 4 of `LEDn_ON_H` and `LEDn_OFF_H`) that produce a true
+/// 100% or 0% output, which cannot be reached through the 12-bit counters alone.
+/// When both bits are set, full off takes precedence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Output is always on.
+    FullOn,
+    /// Output is always off.
+    FullOff,
+    /// Output turns on when the counter reaches `on` and off when it reaches `off`.
+    /// Both values are 12-bit counts (`0..4095`).
+    Pwm { on: u16, off: u16 },
+}
+
+impl Output {
+
+    /// Encodes this output into the `[ON_L, ON_H, OFF_L, OFF_H]` register layout.
+    /// PWM counts are truncated to 12 bits so they never flip a full on/off bit.
+    ///
+    /// ```
+    /// # extern crate rust_pca9685;
+    /// # use rust_pca9685::channel::output::Output;
+    /// #
+    /// assert_eq!([0x99, 0x01, 0xcc, 0x04], Output::Pwm { on: 0x199, off: 0x4cc }.to_bytes());
+    /// assert_eq!([0x00, 0x10, 0x00, 0x00], Output::FullOn.to_bytes());
+    /// assert_eq!([0x00, 0x00, 0x00, 0x10], Output::FullOff.to_bytes());
+    /// ```
+    pub fn to_bytes(self) -> [u8; 4] {
+        match self {
+            Output::FullOn => [0x00, constants::LED_FULL, 0x00, 0x00],
+            Output::FullOff => [0x00, 0x00, 0x00, constants::LED_FULL],
+            Output::Pwm { on, off } => [
+                (on & 0xff) as u8,
+                ((on >> 8) & 0x0f) as u8,
+                (off & 0xff) as u8,
+                ((off >> 8) & 0x0f) as u8,
+            ],
+        }
+    }
+
+    /// Decodes an output from bytes in the `[ON_L, ON_H, OFF_L, OFF_H]` register layout.
+    pub fn from_bytes(data: [u8; 4]) -> Output {
+        if data[3] & constants::LED_FULL != 0 {
+            return Output::FullOff;
+        }
+
+        if data[1] & constants::LED_FULL != 0 {
+            return Output::FullOn;
+        }
+
+        Output::Pwm {
+            on: (data[0] as u16) | (((data[1] & 0x0f) as u16) << 8),
+            off: (data[2] as u16) | (((data[3] & 0x0f) as u16) << 8),
+        }
+    }
+
+}
+
+impl From<(u16, u16)> for Output {
+
+    /// Creates an `Output::Pwm` from an `(on, off)` pair.
+    fn from((on, off): (u16, u16)) -> Output {
+        Output::Pwm { on, off }
+    }
+
+}
@@ -0,0 +1,71 @@
+//! Phase offsets between channels.
+//!
+//! By default every channel turns on at count 0, so all outputs switch on at the
+//! same instant each period. With many servos or LEDs on one board that causes a
+//! current spike on the supply rail. A `PhaseOffset` gives each channel its own ON
+//! count, and the controller's width based setters place OFF at `ON + width` (modulo
+//! 4096) so the pulse width stays the same.
+
+use crate::channel::output::Output;
+use crate::constants;
+
+/// Strategy for choosing the ON count of each channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PhaseOffset {
+    /// Every channel turns on at count 0.
+    #[default]
+    None,
+    /// Channels are spread evenly over the period, channel `n` turns on at `n * 256`.
+    Even,
+    /// Channel `n` turns on at `n * delay` counts, wrapping around the period.
+    PerChannel(u16),
+    /// Channel `n` turns on at count `offsets[n]`, wrapping around the period.
+    Custom([u16; constants::NUM_CHANNELS as usize]),
+}
+
+impl PhaseOffset {
+
+    /// Returns the ON count (`0..4095`) for channel `channel_num`.
+    ///
+    /// ```
+    /// # extern crate rust_pca9685;
+    /// # use rust_pca9685::channel::phase::PhaseOffset;
+    /// #
+    /// assert_eq!(0, PhaseOffset::None.on_count(5));
+    /// assert_eq!(1280, PhaseOffset::Even.on_count(5));
+    /// assert_eq!(904, PhaseOffset::PerChannel(1000).on_count(5));
+    /// ```
+    pub fn on_count(&self, channel_num: u8) -> u16 {
+        let steps = constants::STEP_SIZE as u32;
+        let channel_num = channel_num as u32 % constants::NUM_CHANNELS as u32;
+
+        let on = match *self {
+            PhaseOffset::None => 0,
+            PhaseOffset::Even => channel_num * steps / constants::NUM_CHANNELS as u32,
+            PhaseOffset::PerChannel(delay) => channel_num * delay as u32,
+            PhaseOffset::Custom(offsets) => offsets[channel_num as usize] as u32,
+        };
+
+        (on % steps) as u16
+    }
+
+    /// Returns the output for channel `channel_num` to be on for `width` counts of every period.
+    /// A `width` of 4096 or more is `Output::FullOn`.
+    ///
+    /// ```
+    /// # extern crate rust_pca9685;
+    /// # use rust_pca9685::channel::{ output::Output, phase::PhaseOffset };
+    /// #
+    /// assert_eq!(Output::Pwm { on: 3840, off: 204 }, PhaseOffset::Even.output(15, 460));
+    /// ```
+    pub fn output(&self, channel_num: u8, width: u16) -> Output {
+        let steps = constants::STEP_SIZE as u16;
+        if width >= steps {
+            return Output::FullOn;
+        }
+
+        let on = self.on_count(channel_num);
+        Output::Pwm { on, off: (on + width) % steps }
+    }
+
+}
@@ -0,0 +1,103 @@
+//! Tracks which channels of a controller have been handed out.
+//!
+//! `Controller::take_servo` and `Controller::take_led` claim a bit in a 16-bit
+//! allocation mask and wrap the channel in a `ClaimedChannel`, which clears the
+//! bit again when it is dropped. A channel can only be claimed once at a time,
+//! so two subsystems can't end up driving the same output.
+
+use core::ops::{ Deref, DerefMut };
+use core::sync::atomic::{ AtomicU16, Ordering };
+use std::sync::Arc;
+
+use crate::{
+    constants,
+    channel::base::Channel,
+};
+
+/// A 16-bit allocation mask, shared with every `ClaimedChannel` handed out from it.
+#[derive(Clone, Debug, Default)]
+pub struct ChannelRegistry {
+    mask: Arc<AtomicU16>,
+}
+
+impl ChannelRegistry {
+
+    /// Creates a `ChannelRegistry` with every channel free.
+    pub fn new() -> ChannelRegistry {
+        ChannelRegistry::default()
+    }
+
+    /// Returns the allocation mask, bit `n` is set while channel `n` is claimed.
+    pub fn mask(&self) -> u16 {
+        self.mask.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether `channel_num` is currently claimed.
+    pub fn is_claimed(&self, channel_num: u8) -> bool {
+        channel_num < constants::NUM_CHANNELS as u8 && self.mask() & (1 << channel_num) != 0
+    }
+
+    /// Marks `channel_num` as claimed. Returns `false` if it already was.
+    /// Expects `channel_num` to be in `0..15`.
+    pub(crate) fn claim(&self, channel_num: u8) -> bool {
+        let bit = 1 << channel_num;
+        self.mask.fetch_or(bit, Ordering::SeqCst) & bit == 0
+    }
+
+    /// Marks `channel_num` as free again.
+    fn release(&self, channel_num: u8) {
+        self.mask.fetch_and(!(1 << channel_num), Ordering::SeqCst);
+    }
+
+    /// Wraps `channel`, which must have been claimed from this registry.
+    pub(crate) fn wrap<C: Channel>(&self, channel: C) -> ClaimedChannel<C> {
+        ClaimedChannel{
+            channel,
+            registry: self.clone(),
+        }
+    }
+
+}
+
+/// A channel claimed from a `Controller`. Derefs to the wrapped `ServoChannel` or
+/// `LedChannel` and releases the channel when dropped.
+#[derive(Debug)]
+pub struct ClaimedChannel<C: Channel> {
+    channel: C,
+    registry: ChannelRegistry,
+}
+
+impl<C: Channel> Channel for ClaimedChannel<C> {
+
+    /// Returns the channel index of the wrapped channel.
+    fn channel_num(&self) -> u8 {
+        self.channel.channel_num()
+    }
+
+}
+
+impl<C: Channel> Deref for ClaimedChannel<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.channel
+    }
+
+}
+
+impl<C: Channel> DerefMut for ClaimedChannel<C> {
+
+    fn deref_mut(&mut self) -> &mut C {
+        &mut self.channel
+    }
+
+}
+
+impl<C: Channel> Drop for ClaimedChannel<C> {
+
+    fn drop(&mut self) {
+        debug!("releasing channel {}", self.channel.channel_num());
+        self.registry.release(self.channel.channel_num());
+    }
+
+}
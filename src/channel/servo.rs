@@ -1,12 +1,15 @@
+use core::fmt;
+#[cfg(feature = "std")]
 use env_logger;
-use std::fmt;
+use libm;
 
-use ::channel::{
+use crate::channel::{
     base::Channel,
     errors,
 };
 
 
+/// Pulse range of a servo, in µs, as found on the servo's datasheet.
 #[derive(Clone, Copy, Debug)]
 pub struct ServoSettings {
     min: u16,
@@ -15,20 +18,22 @@ pub struct ServoSettings {
 
 impl ServoSettings {
 
+    /// Creates `ServoSettings` for a servo that moves through its range between
+    /// pulses of `min` and `max` µs.
     pub fn new(min: u16, max: u16) -> ServoSettings {
         ServoSettings{
-            min: min,
-            max: max,
+            min,
+            max,
         }
     }
 
-    /// Returns a `ServoSettings` struct with default values.
+    /// Returns a `ServoSettings` struct with default values, a 500 µs to 2500 µs pulse range.
     /// Note that these are not necessarily safe defaults -- it greatly depends on the servo
-    /// that is being controlled! For example, the Tower Pro SG90 
+    /// that is being controlled! For example, the Tower Pro SG90 is documented as 1000 µs to 2000 µs.
     pub fn defaults() -> ServoSettings {
         ServoSettings{
-            min: 0,
-            max: 4095,
+            min: 500,
+            max: 2500,
         }
     }
 
@@ -55,7 +60,7 @@ impl ServoChannel {
 
         Ok(
             ServoChannel{
-                channel_num: channel_num,
+                channel_num,
                 settings: ServoSettings::defaults(),
             }
         )
@@ -85,14 +90,14 @@ impl ServoChannel {
         None
     }
 
-    /// Returns the minimum value that is allowable for this `ServoChannel`.
+    /// Returns the minimum pulse width in µs that is allowable for this `ServoChannel`.
     pub fn minimum_value(self) -> u16 {
-        return self.settings.min;
+        self.settings.min
     }
 
-    /// Returns the maximum value that is allowable for this `ServoChannel`.
+    /// Returns the maximum pulse width in µs that is allowable for this `ServoChannel`.
     pub fn maximum_value(self) -> u16 {
-        return self.settings.max;
+        self.settings.max
     }
 
     /// Given a pulse time (µs), calculate the angle in degrees that the servo
@@ -100,11 +105,11 @@ impl ServoChannel {
     /// 
     /// Based on Pimoroni's [pantilthat.pantilt module](https://github.com/pimoroni/pantilt-hat/blob/master/library/pantilthat/pantilt.py#L139)
     pub fn pulse_time_to_degrees(self, pulse: u16) -> Result<f32, errors::ValueRangeError> {
+        #[cfg(feature = "std")]
         let _ = env_logger::try_init();
 
-        let valid = self.pulse_value_in_range(pulse as f32);
-        if valid.is_some() {
-            return Err(valid.unwrap());
+        if let Some(err) = self.pulse_value_in_range(pulse as f32) {
+            return Err(err);
         }
 
         debug!("pulse value {} is valid", pulse);
@@ -124,7 +129,7 @@ impl ServoChannel {
         let angle: f32 = angle * 180.0;
         debug!("scaled angle is {:.2}", angle);
 
-        let angle: f32 = angle.round();
+        let angle: f32 = libm::roundf(angle);
         debug!("rounded angle is {:.2}", angle);
 
         let angle: f32 = angle - 90.0;
@@ -138,7 +143,7 @@ impl ServoChannel {
     /// 
     /// Based on Pimoroni's [pantilthat.pantilt module](https://github.com/pimoroni/pantilt-hat/blob/master/library/pantilthat/pantilt.py#L139)
     pub fn degrees_to_pulse_time(self, angle: f32) -> Result<u16, errors::ValueRangeError> {
-        if angle < -90.0 || angle > 90.0 {
+        if !(-90.0..=90.0).contains(&angle) {
             return Err(errors::ValueRangeError::new(
                 errors::Value::Int(-90),
                 errors::Value::Int(90),
@@ -160,10 +165,10 @@ impl ServoChannel {
         let scale: f32 = servo_range / 180.0;
         debug!("range scale is: {:.2}", scale);
 
-        let scaled: f32 = (angle as f32) * scale;
+        let scaled: f32 = angle * scale;
         debug!("scaled angle to servo differential: {:.2}", scaled);
 
-        let pulse: u16 = min + (scaled as u16);
+        let pulse: u16 = min + (libm::roundf(scaled) as u16);
         debug!("finalized pulse: {}", pulse);
 
         Ok(pulse)
@@ -175,7 +180,7 @@ impl Channel for ServoChannel {
 
     /// Returns the channel index for this `ServoChannel`
     fn channel_num(&self) -> u8 {
-        return self.channel_num;
+        self.channel_num
     }
 
 }
@@ -0,0 +1,104 @@
+use core::fmt;
+
+use crate::{
+    constants,
+    channel::output::Output,
+};
+
+/// Decoded contents of a channel's registers, as read back from the controller.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChannelState {
+    channel_num: u8,
+    data: [u8; 4],
+    tick_length: f32,
+}
+
+impl ChannelState {
+
+    /// Creates a `ChannelState` for `channel_num` from the raw `[ON_L, ON_H, OFF_L, OFF_H]`
+    /// register bytes. `tick_length` is the length of one counter step in µs at the
+    /// controller's PWM frequency, see `Controller::tick_length`.
+    pub fn new(channel_num: u8, data: [u8; 4], tick_length: f32) -> ChannelState {
+        ChannelState{
+            channel_num,
+            data,
+            tick_length,
+        }
+    }
+
+    /// Returns the channel index this state was read from.
+    pub fn channel_num(&self) -> u8 {
+        self.channel_num
+    }
+
+    /// Returns the raw `[ON_L, ON_H, OFF_L, OFF_H]` register bytes.
+    pub fn bytes(&self) -> [u8; 4] {
+        self.data
+    }
+
+    /// Returns the 12-bit counter value at which the output turns on.
+    pub fn on(&self) -> u16 {
+        (self.data[0] as u16) | (((self.data[1] & 0x0f) as u16) << 8)
+    }
+
+    /// Returns the 12-bit counter value at which the output turns off.
+    pub fn off(&self) -> u16 {
+        (self.data[2] as u16) | (((self.data[3] & 0x0f) as u16) << 8)
+    }
+
+    /// Returns whether the full on bit is set.
+    pub fn full_on(&self) -> bool {
+        self.data[1] & constants::LED_FULL != 0
+    }
+
+    /// Returns whether the full off bit is set. Full off takes precedence over full on.
+    pub fn full_off(&self) -> bool {
+        self.data[3] & constants::LED_FULL != 0
+    }
+
+    /// Returns the `Output` the channel is driving.
+    pub fn output(&self) -> Output {
+        Output::from_bytes(self.data)
+    }
+
+    /// Returns the number of counter steps per period that the output is on.
+    pub fn on_ticks(&self) -> u16 {
+        match self.output() {
+            Output::FullOff => 0,
+            Output::FullOn => constants::STEP_SIZE as u16,
+            Output::Pwm { on, off } => {
+                let steps = constants::STEP_SIZE as u16;
+                (off + steps - on) % steps
+            },
+        }
+    }
+
+    /// Returns the fraction (`0.0..1.0`) of each period that the output is on.
+    pub fn duty_cycle(&self) -> f32 {
+        self.on_ticks() as f32 / constants::STEP_SIZE
+    }
+
+    /// Returns how long the output is on during each period, in µs.
+    pub fn pulse_width(&self) -> f32 {
+        self.on_ticks() as f32 * self.tick_length
+    }
+
+}
+
+impl fmt::Display for ChannelState {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ChannelState<{}: ON: {:#05x}, OFF: {:#05x}, FULL_ON: {}, FULL_OFF: {}, DUTY: {:.2}%, PULSE: {:.1} µs>",
+            self.channel_num,
+            self.on(),
+            self.off(),
+            self.full_on(),
+            self.full_off(),
+            self.duty_cycle() * 100.0,
+            self.pulse_width(),
+        )
+    }
+
+}
@@ -10,9 +10,18 @@ pub const PCA9685_SLAVE_ADDRESS: u16 = 0x40;
 /// PCA9685 internal clock oscillation frequency
 pub const OSCILLATION_FREQ: f32 = 25000000.0;
 
+/// Fastest clock the PCA9685 accepts on the EXTCLK pin
+pub const MAX_EXTCLK_FREQ: f32 = 50000000.0;
+
 /// Number of steps available on a channel
 pub const STEP_SIZE: f32 = 4096.0;
 
+/// Smallest value the controller accepts in the PRE_SCALE register
+pub const PRE_SCALE_MIN: u8 = 0x03;
+
+/// Largest value the controller accepts in the PRE_SCALE register
+pub const PRE_SCALE_MAX: u8 = 0xFF;
+
 /// Number of channels the PCA9685 has
 pub const NUM_CHANNELS: i64 = 16;
 
@@ -26,6 +35,14 @@ pub const I2C_MASTER_ADDRESS: u16 = 0x00;
 /// I2C master soft reset command
 pub const SOFT_RESET: u8 = 0x06;
 
+/// Power-on LED All Call I2C address, answered by every PCA9685 with `ALLCALL` set
+pub const ALL_CALL_ADDRESS: u16 = 0x70;
+
+/// Power-on I2C bus subaddresses 1..3
+pub const SUBADDR_1_ADDRESS: u16 = 0x71;
+pub const SUBADDR_2_ADDRESS: u16 = 0x72;
+pub const SUBADDR_3_ADDRESS: u16 = 0x74;
+
 //
 // REGISTERS
 //
@@ -39,6 +56,8 @@ pub const MODE_2: u8 = 0x01;
 pub const SUBADDR_1: u8 = 0x02;
 pub const SUBADDR_2: u8 = 0x03;
 pub const SUBADDR_3: u8 = 0x04;
+/// LED All Call I2C bus address
+pub const ALL_CALL_ADDR: u8 = 0x05;
 pub const PRE_SCALE: u8 = 0xFE;
 
 /// Base values for a single LED register
@@ -47,6 +66,9 @@ pub const BASE_LED_ON_HIGH: u8 = 0x07;
 pub const BASE_LED_OFF_LOW: u8 = 0x08;
 pub const BASE_LED_OFF_HIGH: u8 = 0x09;
 
+/// Full on / full off bit in the `LEDn_ON_H` and `LEDn_OFF_H` registers
+pub const LED_FULL: u8 = 0x10;
+
 // Registers for controlling state of all LEDs
 pub const ALL_LED_ON_LOW: u8 = 0xFA;
 pub const ALL_LED_ON_HIGH: u8 = 0xFB;
@@ -61,14 +83,37 @@ pub const ALL_LED_OFF_HIGH: u8 = 0xFD;
 // (MODE_1 register -- bit 7 is set)
 pub const RESTART: u8 = 0x80;
 
+// Use the EXTCLK pin as the clock source instead of the internal oscillator
+// (MODE_1 register -- bit 6 is set)
+pub const EXTCLK: u8 = 0x40;
+
+// Auto-increment the control register address after each read or write
+// (MODE_1 register -- bit 5 is set)
+pub const AI: u8 = 0x20;
+
 // Signal a sleep to the controller, puts the oscillator in a low-power state
 // and turns off the oscillator (MODE_1 register -- bit 4 is set)
 pub const SLEEP: u8 = 0x10;
 
-// Signal for the controller to either output change on STOP command (default) or
-// output change on ACK (MODE_2 register -- bit 3 is set)
-pub const OUTPUT_CHANGE: u8 = 0x0C;
+// Respond to the I2C bus subaddresses stored in SUBADDR_1..3
+// (MODE_1 register -- bits 3, 2 and 1)
+pub const SUB1: u8 = 0x08;
+pub const SUB2: u8 = 0x04;
+pub const SUB3: u8 = 0x02;
 
+// Respond to the LED All Call I2C bus address (MODE_1 register -- bit 0 is set)
 pub const ALL_CALL: u8 = 0x01;
+
+// Invert the output logic state (MODE_2 register -- bit 4 is set)
 pub const INVRT: u8 = 0x10;
-pub const OUTDRV: u8 = 0x04;
\ No newline at end of file
+
+// Signal for the controller to either output change on STOP command (default) or
+// output change on ACK (MODE_2 register -- bit 3 is set)
+pub const OUTPUT_CHANGE: u8 = 0x08;
+
+// Configure outputs as totem pole instead of open-drain (MODE_2 register -- bit 2 is set)
+pub const OUTDRV: u8 = 0x04;
+
+// Output state when /OE = 1 (MODE_2 register -- bits 1 and 0)
+pub const OUTNE_1: u8 = 0x02;
+pub const OUTNE_0: u8 = 0x01;
@@ -1,9 +1,31 @@
+use embedded_hal::delay::DelayNs;
+#[cfg(feature = "std")]
 use env_logger;
-use i2cdev::core::*;
-use std::{thread, time};
+use libm;
 
-use ::constants;
-use ::channel::base::Channel;
+use crate::batch::Batch;
+use crate::constants;
+#[cfg(feature = "std")]
+use crate::delay::StdDelay;
+use crate::device::Device;
+use crate::error::Error;
+use crate::channel::{
+    base::Channel,
+    errors,
+    output::Output,
+    phase::PhaseOffset,
+    servo::ServoChannel,
+    state::ChannelState,
+};
+use crate::channel::led::{ Brightness, LedChannel };
+#[cfg(feature = "std")]
+use crate::channel::{
+    registry::{ ChannelRegistry, ClaimedChannel },
+    servo::ServoSettings,
+};
+use crate::mode::{ Mode1, Mode2, OutputConfig, UpdateOn };
+use crate::reset::SoftReset;
+use crate::rgb::{ ColorLed, ComponentOutput };
 
 /// Calculates a value to insert into PRE_SCALE register where
 /// `update_rate` is the output modulation frequency in Hertz.
@@ -21,49 +43,608 @@ use ::channel::base::Channel;
 /// assert_eq!(0x1e, prescale_value);
 /// ```
 pub fn calculate_prescale_value(update_rate: f32) -> u8 {
-    let base_val = constants::OSCILLATION_FREQ / (constants::STEP_SIZE * update_rate);
-    return (base_val.round() - 1.0) as u8;
+    prescale_from(constants::OSCILLATION_FREQ, update_rate) as u8
 }
 
-#[derive(Debug)]
-pub struct Controller<'a, T: I2CDevice + 'a> {
-    device: &'a mut T,
+/// Unrounded-to-`u8` PRE_SCALE value for `update_rate` given an oscillator running at `oscillator_freq`.
+fn prescale_from(oscillator_freq: f32, update_rate: f32) -> f32 {
+    let base_val = oscillator_freq / (constants::STEP_SIZE * update_rate);
+    libm::roundf(base_val) - 1.0
+}
+
+/// Output modulation frequency for `prescale` given an oscillator running at `oscillator_freq`.
+pub(crate) fn frequency_from(oscillator_freq: f32, prescale: u8) -> f32 {
+    oscillator_freq / (constants::STEP_SIZE * (prescale as f32 + 1.0))
+}
+
+/// Length in µs of one step of the 12-bit counter for `prescale` given an oscillator running at `oscillator_freq`.
+pub(crate) fn tick_length_from(oscillator_freq: f32, prescale: u8) -> f32 {
+    (prescale as f32 + 1.0) * 1000000.0 / oscillator_freq
+}
+
+/// Calculates the PRE_SCALE value for `update_rate` given an oscillator running at `oscillator_freq`,
+/// failing when it falls outside of the values the controller accepts (`0x03..0xFF`).
+pub(crate) fn checked_prescale_from(oscillator_freq: f32, update_rate: f32) -> Result<u8, errors::ValueRangeError> {
+    let prescale = prescale_from(oscillator_freq, update_rate);
+    if !(constants::PRE_SCALE_MIN as f32..=constants::PRE_SCALE_MAX as f32).contains(&prescale) {
+        // Rates that round to the PRE_SCALE limits, as reported in the error
+        let min_rate = oscillator_freq / (constants::STEP_SIZE * (constants::PRE_SCALE_MAX as f32 + 1.5));
+        let max_rate = oscillator_freq / (constants::STEP_SIZE * (constants::PRE_SCALE_MIN as f32 + 0.5));
+        return Err(errors::ValueRangeError::new(
+            errors::Value::Float(min_rate),
+            errors::Value::Float(max_rate),
+            errors::Value::Float(update_rate),
+        ));
+    }
+
+    Ok(prescale as u8)
+}
+
+/// Like [calculate_prescale_value][calculate_prescale_value], but returns an error
+/// when the PRE_SCALE value for `update_rate` falls outside of `0x03..0xFF`
+/// (about 24Hz to 1526Hz with the internal oscillator) instead of wrapping.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rust_pca9685;
+/// # use rust_pca9685::controller::checked_prescale_value;
+/// #
+/// assert_eq!(0x1e, checked_prescale_value(200.0).unwrap());
+/// assert!(checked_prescale_value(2000.0).is_err());
+/// ```
+///
+/// [calculate_prescale_value]: fn.calculate_prescale_value.html
+pub fn checked_prescale_value(update_rate: f32) -> Result<u8, errors::ValueRangeError> {
+    checked_prescale_from(constants::OSCILLATION_FREQ, update_rate)
+}
+
+/// Calculates the output modulation frequency in Hertz that results from
+/// `prescale` being stored in the PRE_SCALE register. This is the inverse of
+/// [calculate_prescale_value][calculate_prescale_value].
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rust_pca9685;
+/// # use rust_pca9685::controller::prescale_to_frequency;
+/// #
+/// let update_rate = prescale_to_frequency(0x1e);
+/// assert_eq!(196.9, (update_rate * 10.0).round() / 10.0);
+/// ```
+///
+/// [calculate_prescale_value]: fn.calculate_prescale_value.html
+pub fn prescale_to_frequency(prescale: u8) -> f32 {
+    frequency_from(constants::OSCILLATION_FREQ, prescale)
+}
+
+/// Calculates the real oscillator frequency of a controller that was programmed
+/// with `prescale` and whose output was measured at `measured_rate` Hertz.
+///
+/// Internal oscillators commonly drift by several percent from the nominal 25MHz,
+/// so measuring the output with a scope or frequency counter and feeding the result
+/// back through [Controller::set_oscillator_frequency][set_oscillator_frequency]
+/// makes every following prescale and pulse calculation match reality.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate rust_pca9685;
+/// # use rust_pca9685::controller::calibrate_oscillator_frequency;
+/// #
+/// // Programmed for 50Hz (prescale 0x79), but the scope reads 50.5Hz
+/// let oscillator_freq = calibrate_oscillator_frequency(0x79, 50.5);
+/// assert_eq!(25235456.0, oscillator_freq);
+/// ```
+///
+/// [set_oscillator_frequency]: struct.Controller.html#method.set_oscillator_frequency
+pub fn calibrate_oscillator_frequency(prescale: u8, measured_rate: f32) -> f32 {
+    measured_rate * constants::STEP_SIZE * (prescale as f32 + 1.0)
+}
+
+/// Returns the register and `MODE_1` flag of I2C bus subaddress `n` (1, 2 or 3).
+fn subaddress_register(n: u8) -> Result<(u8, Mode1), errors::ValueRangeError> {
+    match n {
+        1 => Ok((constants::SUBADDR_1, Mode1::SUB1)),
+        2 => Ok((constants::SUBADDR_2, Mode1::SUB2)),
+        3 => Ok((constants::SUBADDR_3, Mode1::SUB3)),
+        _ => Err(errors::ValueRangeError::new(
+            errors::Value::Int(1),
+            errors::Value::Int(3),
+            errors::Value::Int(n as i32),
+        )),
+    }
+}
+
+/// Converts a 7-bit I2C address into the value stored in a subaddress register,
+/// where the address sits in bits 7:1.
+fn address_to_register(address: u16) -> Result<u8, errors::ValueRangeError> {
+    if address > 0x7f {
+        return Err(errors::ValueRangeError::new(
+            errors::Value::Int(0),
+            errors::Value::Int(0x7f),
+            errors::Value::Int(address as i32),
+        ));
+    }
+
+    Ok((address as u8) << 1)
 }
 
-impl<'a, T: I2CDevice + 'a> Controller<'a, T> {
+/// Writes raw register bytes to several channels on `device`, bursting runs of consecutive
+/// channels when `auto_increment` is enabled. `to_bytes` maps each entry in `values` to its
+/// channel number and register bytes. Returns `Error::IndexRange` without writing anything
+/// if any channel number is outside of `0..15`.
+pub(crate) fn write_channel_bytes<T, V, F>(device: &mut T, auto_increment: bool, values: &[V], to_bytes: F) -> Result<(), Error<T::Error>>
+    where T: Device, F: Fn(&V) -> (u8, [u8; 4])
+{
+    if values.iter().any(|value| to_bytes(value).0 >= constants::NUM_CHANNELS as u8) {
+        return Err(Error::IndexRange(errors::IndexRangeError::new()));
+    }
 
-    pub fn new(dev: &'a mut T) -> Controller<'a, T> {
-        let mut c = Controller{ device: dev };
-        {
-            c.set_up().unwrap();
+    let mut start = 0;
+    while start < values.len() {
+        // Extend the run for as long as the channel numbers are consecutive
+        let mut end = start + 1;
+        while end < values.len() && to_bytes(&values[end]).0 == to_bytes(&values[end - 1]).0 + 1 {
+            end += 1;
         }
 
-        return c;
+        write_channel_run(device, auto_increment, &values[start..end], &to_bytes)?;
+        start = end;
+    }
+
+    Ok(())
+}
+
+/// Writes a run of consecutive channels, starting at the first channel in `run`.
+/// A run never spans more than `NUM_CHANNELS` channels.
+fn write_channel_run<T, V, F>(device: &mut T, auto_increment: bool, run: &[V], to_bytes: &F) -> Result<(), Error<T::Error>>
+    where T: Device, F: Fn(&V) -> (u8, [u8; 4])
+{
+    let base = constants::BASE_LED_ON_LOW + 4 * to_bytes(&run[0]).0;
+
+    if !auto_increment {
+        for (i, value) in run.iter().enumerate() {
+            let register = base + 4 * i as u8;
+            for (offset, byte) in to_bytes(value).1.iter().enumerate() {
+                device.write_register(register + offset as u8, *byte).map_err(Error::Bus)?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    let mut buf = [0u8; 4 * constants::NUM_CHANNELS as usize];
+    for (i, value) in run.iter().enumerate() {
+        buf[4 * i..4 * i + 4].copy_from_slice(&to_bytes(value).1);
+    }
+
+    debug!("burst writing {} channels starting at {:#04x}", run.len(), base);
+    device.write_registers(base, &buf[..4 * run.len()]).map_err(Error::Bus)
+}
+
+#[derive(Debug)]
+pub struct Controller<T: Device, D: DelayNs> {
+    device: T,
+    delay: D,
+    output_config: OutputConfig,
+    phase_offset: PhaseOffset,
+    auto_increment: bool,
+    oscillator_freq: f32,
+    prescale: u8,
+    #[cfg(feature = "std")]
+    registry: ChannelRegistry,
+}
+
+#[cfg(feature = "std")]
+impl<T: Device> Controller<T, StdDelay> {
+
+    /// Creates a `Controller` for the PCA9685 behind `dev` and puts it into a known state,
+    /// sleeping the current thread while the oscillator settles.
+    /// Returns `Error::NotInitialized` if the controller does not wake up during set up.
+    pub fn new(dev: T) -> Result<Controller<T, StdDelay>, Error<T::Error>> {
+        Controller::new_with_delay(dev, StdDelay)
+    }
+
+}
+
+impl<T: Device, D: DelayNs> Controller<T, D> {
+
+    /// Creates a `Controller` for the PCA9685 behind `dev` and puts it into a known state.
+    /// `delay` is used to wait on the oscillator, here and whenever the controller is restarted.
+    /// The `Controller` owns `dev` from here on, `into_inner` hands it back.
+    /// Returns `Error::NotInitialized` if the controller does not wake up during set up.
+    pub fn new_with_delay(dev: T, delay: D) -> Result<Controller<T, D>, Error<T::Error>> {
+        Controller::new_with_output_config(dev, delay, OutputConfig::default())
+    }
+
+    /// Like `new_with_delay`, but configures the output drivers with `config`
+    /// instead of the default totem-pole outputs.
+    pub fn new_with_output_config(dev: T, delay: D, config: OutputConfig) -> Result<Controller<T, D>, Error<T::Error>> {
+        let mut c = Controller{
+            device: dev,
+            delay,
+            output_config: config,
+            phase_offset: PhaseOffset::None,
+            auto_increment: true,
+            oscillator_freq: constants::OSCILLATION_FREQ,
+            prescale: 0,
+            #[cfg(feature = "std")]
+            registry: ChannelRegistry::new(),
+        };
+        c.set_up()?;
+
+        Ok(c)
     }
 
     /// Performs some initial set up on the PCA9685.
-    /// - Set `OUTDRV` on `MODE_2`
-    /// - Set `ALLCALL` on `MODE_1`
+    /// - Write the `OutputConfig` to `MODE_2`
+    /// - Set `ALLCALL` and `AI` on `MODE_1`
     /// - Sleep waiting on oscillator
     /// - Read `MODE_1` back
     /// - Unset the `SLEEP` bit on `MODE_1` to wake up controller
     /// - Sleep waiting on oscillator
+    /// - Read `PRE_SCALE` to know the current output modulation frequency
     /// 
     /// This puts the controller `MODE_*` registers into a known state at
     /// the beginning of operation.
     /// 
     /// At the beginning of operation, you can expect:
-    /// - `MODE_1` *should* be set to `0x01` (ALL_CALL)
-    /// - `MODE_2` *should* be set to `0x04` (OUTDRV)
-    fn set_up(&mut self) -> Result<(), T::Error> {
-        self.device.smbus_write_byte_data(constants::MODE_2, constants::OUTDRV)?;
-        self.device.smbus_write_byte_data(constants::MODE_1, constants::ALL_CALL)?;
-        thread::sleep(time::Duration::from_millis(5));
-
-        let mode1 = self.device.smbus_read_byte_data(constants::MODE_1)?;
-        let mode1 = mode1 & (!constants::SLEEP);
-        self.device.smbus_write_byte_data(constants::MODE_1, mode1)?;
-        thread::sleep(time::Duration::from_millis(5));;
+    /// - `MODE_1` *should* be set to `0x21` (ALL_CALL | AI)
+    /// - `MODE_2` *should* be set to `0x04` (OUTDRV) with the default `OutputConfig`
+    fn set_up(&mut self) -> Result<(), Error<T::Error>> {
+        self.write_mode2(self.output_config.mode2())?;
+        self.write_mode1(Mode1::ALLCALL | Mode1::AI)?;
+        self.delay.delay_ms(5);
+
+        self.modify_mode1(|mode| mode - Mode1::SLEEP)?;
+        self.delay.delay_ms(5);
+
+        if self.read_mode1()?.contains(Mode1::SLEEP) {
+            return Err(Error::NotInitialized);
+        }
+
+        self.prescale = self.read_register(constants::PRE_SCALE)?;
+
+        Ok(())
+    }
+
+    /// Sends a software reset through the device and brings the controller back up,
+    /// see `reset_and_reinit_with`.
+    pub fn reset_and_reinit(&mut self) -> Result<(), Error<T::Error>>
+        where T: SoftReset
+    {
+        self.reset_and_reinit_with(T::soft_reset)
+    }
+
+    /// Resets the controller with `reset`, waits for it to come out of reset and re-runs
+    /// the initial set up, restoring the configured PWM rate and auto-increment setting.
+    ///
+    /// `reset` is handed the device, so backends without `SoftReset` can supply the slave
+    /// address to return to, e.g. `|dev| reset::soft_reset(dev, Some(0x41))`.
+    /// A software reset reaches every PCA9685 on the bus, and clears `EXTCLK` on this one.
+    /// The oscillator frequency falls back to the internal oscillator's nominal 25MHz and
+    /// the PWM rate is recalculated for it; call `enable_external_clock` or
+    /// `set_oscillator_frequency` again to restore the oscillator. Returns
+    /// `Error::InvalidFrequency` if the internal oscillator cannot reach the old rate.
+    pub fn reset_and_reinit_with<F>(&mut self, reset: F) -> Result<(), Error<T::Error>>
+        where F: FnOnce(&mut T) -> Result<(), Error<T::Error>>
+    {
+        let update_rate = self.prescale_to_frequency(self.prescale);
+        let auto_increment = self.auto_increment;
+
+        reset(&mut self.device)?;
+        self.delay.delay_us(500);
+        self.set_oscillator_frequency(constants::OSCILLATION_FREQ);
+
+        self.set_up()?;
+        self.auto_increment = true;
+
+        if !auto_increment {
+            self.set_auto_increment(false)?;
+        }
+
+        let prescale = self.prescale_value(update_rate).map_err(Error::InvalidFrequency)?;
+        if self.prescale != prescale {
+            self.set_pwm_rate(prescale)?;
+        }
+
+        Ok(())
+    }
+
+    /// Consumes the `Controller` and returns the device it was driving.
+    pub fn into_inner(self) -> T {
+        self.device
+    }
+
+    /// Consumes the `Controller` and returns the device and delay it was driving.
+    pub fn release(self) -> (T, D) {
+        (self.device, self.delay)
+    }
+
+    /// Reads a single register from the controller.
+    fn read_register(&mut self, register: u8) -> Result<u8, Error<T::Error>> {
+        self.device.read_register(register).map_err(Error::Bus)
+    }
+
+    /// Writes a single register on the controller.
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), Error<T::Error>> {
+        self.device.write_register(register, value).map_err(Error::Bus)
+    }
+
+    /// Reads the `MODE_1` register.
+    pub fn read_mode1(&mut self) -> Result<Mode1, Error<T::Error>> {
+        let bits = self.read_register(constants::MODE_1)?;
+        Ok(Mode1::from_bits_truncate(bits))
+    }
+
+    /// Writes `mode` to the `MODE_1` register.
+    ///
+    /// Note that writing `Mode1::RESTART` clears the restart bit on the
+    /// controller, which restarts any channels that were running before `SLEEP`.
+    pub fn write_mode1(&mut self, mode: Mode1) -> Result<(), Error<T::Error>> {
+        self.write_register(constants::MODE_1, mode.bits())
+    }
+
+    /// Reads the `MODE_1` register, passes it through `f` and writes the result back.
+    /// Returns the value that was written.
+    ///
+    /// ```
+    /// # extern crate i2cdev;
+    /// # extern crate rust_pca9685;
+    /// # extern crate embedded_hal;
+    /// # use embedded_hal::delay::DelayNs;
+    /// # use i2cdev::mock::MockI2CDevice;
+    /// # use rust_pca9685::{ controller::Controller, mode::Mode1 };
+    /// # fn example<D: DelayNs>(ctrl: &mut Controller<MockI2CDevice, D>) {
+    /// ctrl.modify_mode1(|mode| mode | Mode1::SUB1).unwrap();
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn modify_mode1<F: FnOnce(Mode1) -> Mode1>(&mut self, f: F) -> Result<Mode1, Error<T::Error>> {
+        let mode = f(self.read_mode1()?);
+        self.write_mode1(mode)?;
+        Ok(mode)
+    }
+
+    /// Reads the `MODE_2` register.
+    pub fn read_mode2(&mut self) -> Result<Mode2, Error<T::Error>> {
+        let bits = self.read_register(constants::MODE_2)?;
+        Ok(Mode2::from_bits_truncate(bits))
+    }
+
+    /// Writes `mode` to the `MODE_2` register.
+    pub fn write_mode2(&mut self, mode: Mode2) -> Result<(), Error<T::Error>> {
+        self.write_register(constants::MODE_2, mode.bits())
+    }
+
+    /// Reads the `MODE_2` register, passes it through `f` and writes the result back.
+    /// Returns the value that was written.
+    pub fn modify_mode2<F: FnOnce(Mode2) -> Mode2>(&mut self, f: F) -> Result<Mode2, Error<T::Error>> {
+        let mode = f(self.read_mode2()?);
+        self.write_mode2(mode)?;
+        Ok(mode)
+    }
+
+    /// Returns the output driver configuration of the controller.
+    pub fn output_config(&self) -> OutputConfig {
+        self.output_config
+    }
+
+    /// Reconfigures the output drivers, leaving the other `MODE_2` flags alone.
+    pub fn set_output_config(&mut self, config: OutputConfig) -> Result<(), Error<T::Error>> {
+        let mode = self.modify_mode2(|mode| (mode - Mode2::INVRT - Mode2::OUTDRV - Mode2::OUTNE) | config.mode2())?;
+        debug!("wrote output config {:#04x} to MODE_2", mode.bits());
+        self.output_config = config;
+
+        Ok(())
+    }
+
+    /// Reads whether channel outputs change on the I2C STOP condition or on ACK.
+    pub fn update_mode(&mut self) -> Result<UpdateOn, Error<T::Error>> {
+        if self.read_mode2()?.contains(Mode2::OCH) {
+            Ok(UpdateOn::Ack)
+        } else {
+            Ok(UpdateOn::Stop)
+        }
+    }
+
+    /// Sets whether channel outputs change on the I2C STOP condition (the power-on
+    /// default) or on the ACK of every register write.
+    pub fn set_update_mode(&mut self, update_on: UpdateOn) -> Result<(), Error<T::Error>> {
+        self.modify_mode2(|mode| match update_on {
+            UpdateOn::Stop => mode - Mode2::OCH,
+            UpdateOn::Ack => mode | Mode2::OCH,
+        })?;
+
+        Ok(())
+    }
+
+    /// Puts the controller into low-power `SLEEP`. The oscillator stops and every
+    /// output turns off, but the channel registers keep their values so `wake`
+    /// can resume them.
+    pub fn sleep(&mut self) -> Result<(), Error<T::Error>> {
+        // Writing a 0 to `RESTART` leaves it untouched, a 1 would clear it
+        let mode = self.modify_mode1(|mode| (mode - Mode1::RESTART) | Mode1::SLEEP)?;
+        debug!("controller asleep, MODE_1 {:#04x}", mode.bits());
+
+        Ok(())
+    }
+
+    /// Returns whether the controller is in `SLEEP`.
+    pub fn is_sleeping(&mut self) -> Result<bool, Error<T::Error>> {
+        Ok(self.read_mode1()?.contains(Mode1::SLEEP))
+    }
+
+    /// Wakes the controller from `SLEEP` following the datasheet restart sequence:
+    /// - Read `MODE_1` and check whether `RESTART` is set
+    /// - Clear `SLEEP`
+    /// - Wait 500 µs for the oscillator to stabilize
+    /// - Write a 1 to `RESTART` if it was set, which resumes every channel
+    ///   with the values it had before going to sleep
+    pub fn wake(&mut self) -> Result<(), Error<T::Error>> {
+        let mode = self.read_mode1()?;
+        self.restart(mode - Mode1::SLEEP - Mode1::RESTART, mode.contains(Mode1::RESTART))
+    }
+
+    /// Writes `awake`, which must have `SLEEP` cleared, waits for the oscillator and
+    /// writes `RESTART` if `restart` is set, see `wake`.
+    fn restart(&mut self, awake: Mode1, restart: bool) -> Result<(), Error<T::Error>> {
+        self.write_mode1(awake)?;
+        self.delay.delay_us(500);
+
+        if restart {
+            self.write_mode1(awake | Mode1::RESTART)?;
+            debug!("restarted PWM channels after sleep");
+        }
+
+        Ok(())
+    }
+
+    /// Programs I2C bus subaddress `n` (1, 2 or 3) to the 7-bit `address`.
+    /// The controller only answers on it after `enable_subaddress(n)`.
+    pub fn set_subaddress(&mut self, n: u8, address: u16) -> Result<(), Error<T::Error>> {
+        let (register, _) = subaddress_register(n)?;
+        let value = address_to_register(address)?;
+
+        debug!("setting subaddress {} to {:#04x}", n, address);
+        self.write_register(register, value)
+    }
+
+    /// Reads back the 7-bit address programmed as I2C bus subaddress `n` (1, 2 or 3).
+    pub fn subaddress(&mut self, n: u8) -> Result<u16, Error<T::Error>> {
+        let (register, _) = subaddress_register(n)?;
+        Ok((self.read_register(register)? >> 1) as u16)
+    }
+
+    /// Makes the controller answer on I2C bus subaddress `n` (1, 2 or 3).
+    pub fn enable_subaddress(&mut self, n: u8) -> Result<(), Error<T::Error>> {
+        let (_, flag) = subaddress_register(n)?;
+        self.modify_mode1(|mode| mode | flag)?;
+        Ok(())
+    }
+
+    /// Stops the controller from answering on I2C bus subaddress `n` (1, 2 or 3).
+    pub fn disable_subaddress(&mut self, n: u8) -> Result<(), Error<T::Error>> {
+        let (_, flag) = subaddress_register(n)?;
+        self.modify_mode1(|mode| mode - flag)?;
+        Ok(())
+    }
+
+    /// Programs the LED All Call I2C bus address to the 7-bit `address` (`0x70` at power-on).
+    pub fn set_allcall_address(&mut self, address: u16) -> Result<(), Error<T::Error>> {
+        let value = address_to_register(address)?;
+
+        debug!("setting all call address to {:#04x}", address);
+        self.write_register(constants::ALL_CALL_ADDR, value)
+    }
+
+    /// Reads back the 7-bit LED All Call I2C bus address.
+    pub fn allcall_address(&mut self) -> Result<u16, Error<T::Error>> {
+        Ok((self.read_register(constants::ALL_CALL_ADDR)? >> 1) as u16)
+    }
+
+    /// Makes the controller answer on the LED All Call I2C bus address. This is the power-on default.
+    pub fn enable_allcall(&mut self) -> Result<(), Error<T::Error>> {
+        self.modify_mode1(|mode| mode | Mode1::ALLCALL)?;
+        Ok(())
+    }
+
+    /// Stops the controller from answering on the LED All Call I2C bus address.
+    pub fn disable_allcall(&mut self) -> Result<(), Error<T::Error>> {
+        self.modify_mode1(|mode| mode - Mode1::ALLCALL)?;
+        Ok(())
+    }
+
+    /// Returns the oscillator frequency in Hertz that is used for prescale and pulse calculations.
+    pub fn oscillator_frequency(&self) -> f32 {
+        self.oscillator_freq
+    }
+
+    /// Overrides the oscillator frequency in Hertz used for prescale and pulse calculations.
+    /// This does not touch the controller; use it to apply a calibrated value, see
+    /// [calibrate_oscillator_frequency][calibrate_oscillator_frequency].
+    ///
+    /// [calibrate_oscillator_frequency]: fn.calibrate_oscillator_frequency.html
+    pub fn set_oscillator_frequency(&mut self, oscillator_freq: f32) {
+        debug!("oscillator frequency set to {:.0}Hz", oscillator_freq);
+        self.oscillator_freq = oscillator_freq;
+    }
+
+    /// Calibrates the oscillator frequency from an output frequency in Hertz that was
+    /// measured while the controller was running at its current PRE_SCALE value.
+    /// Returns the corrected oscillator frequency, which is also applied to this `Controller`.
+    pub fn calibrate(&mut self, measured_rate: f32) -> Result<f32, Error<T::Error>> {
+        self.prescale = self.read_register(constants::PRE_SCALE)?;
+        let oscillator_freq = calibrate_oscillator_frequency(self.prescale, measured_rate);
+        self.set_oscillator_frequency(oscillator_freq);
+
+        Ok(oscillator_freq)
+    }
+
+    /// Switches the controller to the clock on the EXTCLK pin, which runs at `oscillator_freq` Hertz.
+    ///
+    /// Follows the datasheet sequence: put the controller to `SLEEP`, set `EXTCLK` while
+    /// still sleeping, then wake it back up and restart the channels as `wake` does.
+    /// Note that `EXTCLK` can only be cleared again by a power cycle or a software reset.
+    pub fn enable_external_clock(&mut self, oscillator_freq: f32) -> Result<(), Error<T::Error>> {
+        if !(oscillator_freq > 0.0 && oscillator_freq <= constants::MAX_EXTCLK_FREQ) {
+            return Err(Error::InvalidFrequency(errors::ValueRangeError::new(
+                errors::Value::Int(0),
+                errors::Value::Float(constants::MAX_EXTCLK_FREQ),
+                errors::Value::Float(oscillator_freq),
+            )));
+        }
+
+        let old_mode = self.read_mode1()?;
+        let sleep_mode = (old_mode - Mode1::RESTART) | Mode1::SLEEP;
+
+        // `EXTCLK` can only be set while the controller is asleep
+        self.write_mode1(sleep_mode)?;
+        self.write_mode1(sleep_mode | Mode1::EXTCLK)?;
+        debug!("switched to external clock, MODE_1 {:#04x}", (sleep_mode | Mode1::EXTCLK).bits());
+
+        // Resume the channels that were running before the switch
+        let wake_mode = (old_mode - Mode1::RESTART - Mode1::SLEEP) | Mode1::EXTCLK;
+        self.restart(wake_mode, old_mode.contains(Mode1::RESTART))?;
+
+        self.set_oscillator_frequency(oscillator_freq);
+
+        Ok(())
+    }
+
+    /// Calculates the PRE_SCALE value for `update_rate` Hertz using this controller's
+    /// oscillator frequency. Returns an error when it falls outside of `0x03..0xFF`.
+    pub fn prescale_value(&self, update_rate: f32) -> Result<u8, errors::ValueRangeError> {
+        checked_prescale_from(self.oscillator_freq, update_rate)
+    }
+
+    /// Calculates the output modulation frequency in Hertz for `prescale`
+    /// using this controller's oscillator frequency.
+    pub fn prescale_to_frequency(&self, prescale: u8) -> f32 {
+        frequency_from(self.oscillator_freq, prescale)
+    }
+
+    /// Returns whether channel writes use auto-increment block writes.
+    pub fn auto_increment(&self) -> bool {
+        self.auto_increment
+    }
+
+    /// Enables or disables register auto-increment (`AI` on `MODE_1`).
+    ///
+    /// With auto-increment enabled (the default), a channel's four registers are written
+    /// in a single I2C write and `set_channels` bursts contiguous channels together.
+    /// Disable it for I2C adapters that can only perform byte-sized SMBus transfers;
+    /// every register is then written individually.
+    pub fn set_auto_increment(&mut self, enabled: bool) -> Result<(), Error<T::Error>> {
+        self.modify_mode1(|mode| {
+            if enabled {
+                mode | Mode1::AI
+            } else {
+                mode - Mode1::AI
+            }
+        })?;
+        self.auto_increment = enabled;
 
         Ok(())
     }
@@ -76,44 +657,90 @@ impl<'a, T: I2CDevice + 'a> Controller<'a, T> {
     /// values will persist even if the controller is put to `SLEEP`.
     /// 
     /// [calculate_prescale_value]: fn.calculate_prescale_value.html
-    pub fn set_pwm_rate(&mut self, prescale_value: u8) -> Result<(), T::Error> {
+    pub fn set_pwm_rate(&mut self, prescale_value: u8) -> Result<(), Error<T::Error>> {
+        #[cfg(feature = "std")]
         let _ = env_logger::try_init();
 
         // Save the old controller mode for revert
-        let old_mode = self.device.smbus_read_byte_data(constants::MODE_1)?;
-        debug!("MODE_1 is {:#04x} before going to sleep", old_mode);
+        let old_mode = self.read_mode1()?;
+        debug!("MODE_1 is {:#04x} before going to sleep", old_mode.bits());
 
-        // Make a new mode value to sleep the controller
-        // NOTE: `0x7f` carries the `EXTCLK`, `AI`, `SLEEP`, `SUB1`, `SUB2`, `SUB3`, `ALL_CALL` bits
-        // over to the new mode, intentionally eliding over the `RESTART` value
-        let new_mode = (old_mode & 0x7f) | constants::SLEEP;
+        // Make a new mode value to sleep the controller, carrying every bit
+        // over to the new mode except `RESTART`
+        let new_mode = (old_mode - Mode1::RESTART) | Mode1::SLEEP;
 
         // Effectively put the controller to sleep
-        self.device.smbus_write_byte_data(constants::MODE_1, new_mode)?;
-        debug!("wrote {:#04x} to MODE_1", new_mode);
+        self.write_mode1(new_mode)?;
+        debug!("wrote {:#04x} to MODE_1", new_mode.bits());
 
         // Write `prescale_value` to the `PRE_SCALE` register
         debug!("setting output modulation frequency to {} (prescale)", prescale_value);
-        self.device.smbus_write_byte_data(constants::PRE_SCALE, prescale_value)?;
+        self.write_register(constants::PRE_SCALE, prescale_value)?;
+        self.prescale = prescale_value;
 
         // Restore the old `MODE_1` flags
-        self.device.smbus_write_byte_data(constants::MODE_1, old_mode)?;
-        debug!("restored {:#04x} to MODE_1", old_mode);
+        self.write_mode1(old_mode)?;
+        debug!("restored {:#04x} to MODE_1", old_mode.bits());
 
         // Wait for oscillator to stabilize before setting `RESTART`
-        thread::sleep(time::Duration::from_millis(5));
+        self.delay.delay_ms(5);
 
         // Set restart bit; forces all channels to remain in their state when the clock is off
-        let restart_mode = old_mode | constants::RESTART;
-        self.device.smbus_write_byte_data(constants::MODE_1, restart_mode)?;
-        debug!("enable restart mode sets MODE_1 {:#04x}", restart_mode);
+        let restart_mode = old_mode | Mode1::RESTART;
+        self.write_mode1(restart_mode)?;
+        debug!("enable restart mode sets MODE_1 {:#04x}", restart_mode.bits());
 
         Ok(())
     }
 
-    /// Set `channel`'s registers to the on/off values given.
+    /// Reads the PRE_SCALE register and returns the output modulation frequency
+    /// the controller is currently running at, in Hertz.
+    pub fn pwm_rate(&mut self) -> Result<f32, Error<T::Error>> {
+        self.prescale = self.read_register(constants::PRE_SCALE)?;
+        debug!("read prescale {:#04x} from PRE_SCALE", self.prescale);
+
+        Ok(self.prescale_to_frequency(self.prescale))
+    }
+
+    /// Returns the length of a single step of the 12-bit counter in µs,
+    /// based on the last known PRE_SCALE value and the oscillator frequency.
+    pub fn tick_length(&self) -> f32 {
+        tick_length_from(self.oscillator_freq, self.prescale)
+    }
+
+    /// Converts a pulse width in µs into a count of 12-bit counter steps at the current
+    /// PWM frequency. Returns an error when the pulse is longer than one period.
+    pub fn microseconds_to_ticks(&self, pulse_width: f32) -> Result<u16, errors::ValueRangeError> {
+        let period = self.tick_length() * constants::STEP_SIZE;
+        if !(0.0..=period).contains(&pulse_width) {
+            return Err(errors::ValueRangeError::new(
+                errors::Value::Int(0),
+                errors::Value::Float(period),
+                errors::Value::Float(pulse_width),
+            ));
+        }
+
+        let ticks = libm::roundf(pulse_width / self.tick_length());
+        Ok(ticks.min(constants::STEP_SIZE - 1.0) as u16)
+    }
+
+    /// Converts a count of 12-bit counter steps into a pulse width in µs at the current PWM frequency.
+    pub fn ticks_to_microseconds(&self, ticks: u16) -> f32 {
+        ticks as f32 * self.tick_length()
+    }
+
+    /// Sets the controller's output modulation rate to `update_rate` Hertz.
+    /// Returns `Error::InvalidFrequency` without touching the controller
+    /// when `update_rate` is outside of the range the oscillator supports
+    /// (about 24Hz to 1526Hz with the internal 25MHz one).
+    pub fn set_frequency(&mut self, update_rate: f32) -> Result<(), Error<T::Error>> {
+        let prescale_value = self.prescale_value(update_rate).map_err(Error::InvalidFrequency)?;
+        self.set_pwm_rate(prescale_value)
+    }
+
+    /// Set `channel`'s registers to drive `output`.
     /// Each channel has two 12-bit registers -- one for ON and one for OFF.
-    /// `set_channel` takes 2 `u16` values for on and off times and they are modified as such:
+    /// An `(on, off)` pair of `u16` values is accepted as `Output::Pwm` and they are modified as such:
     /// 
     /// ```
     /// let on: u16 = 0xfca;
@@ -129,32 +756,272 @@ impl<'a, T: I2CDevice + 'a> Controller<'a, T> {
     /// assert_eq!(0xba, off_low);
     /// assert_eq!(0x0a, off_high);
     /// ```
-    pub fn set_channel<C: Channel>(&mut self, channel: &mut C, on: u16, off: u16) -> Result<(), T::Error> {
-        let data = [
-            (on & 0xff) as u8,
-            (on >> 8) as u8,
-            (off & 0xff) as u8,
-            (off >> 8) as u8,
-        ];
-        return channel.write_channel(self.device, data);
+    ///
+    /// `Output::FullOn` and `Output::FullOff` set the channel's full on or full off bit instead.
+    ///
+    /// When auto-increment is enabled, all four registers are written in one I2C write.
+    pub fn set_channel<C: Channel, O: Into<Output>>(&mut self, channel: &mut C, output: O) -> Result<(), Error<T::Error>> {
+        let data = output.into().to_bytes();
+        if self.auto_increment {
+            channel.write_channel_block(&mut self.device, data)
+        } else {
+            channel.write_channel(&mut self.device, data)
+        }
     }
 
-    pub fn set_all_channels(&mut self, on: u16, off: u16) -> Result<(), T::Error> {
-        let values = vec![
-            (constants::ALL_LED_ON_LOW, (on & 0xff) as u8),
-            (constants::ALL_LED_ON_HIGH, (on >> 8) as u8),
-            (constants::ALL_LED_OFF_LOW, (off & 0xff) as u8),
-            (constants::ALL_LED_OFF_HIGH, (off >> 8) as u8),
-        ];
+    /// Returns the phase offset strategy used by the width based setters.
+    pub fn phase_offset(&self) -> PhaseOffset {
+        self.phase_offset
+    }
 
-        for value in values {
-            let (register, value) = value;
-            let result = self.device.smbus_write_byte_data(register, value);
-            if result.is_err() {
-                return Err(result.unwrap_err());
+    /// Sets the phase offset strategy used by `set_pulse_width`, `set_duty_cycle` and
+    /// `set_servo_angle`. Channels keep their current output until they are set again.
+    pub fn set_phase_offset(&mut self, phase_offset: PhaseOffset) {
+        self.phase_offset = phase_offset;
+    }
+
+    /// Sets `channel` to be on for `ticks` steps of every period, starting at the
+    /// channel's ON count from the `PhaseOffset`.
+    pub fn set_on_ticks<C: Channel>(&mut self, channel: &mut C, ticks: u16) -> Result<(), Error<T::Error>> {
+        let output = self.phase_offset.output(channel.channel_num(), ticks);
+        self.set_channel(channel, output)
+    }
+
+    /// Sets `channel` to output a pulse of `pulse_width` µs every period, starting
+    /// at the channel's ON count from the `PhaseOffset`.
+    pub fn set_pulse_width<C: Channel>(&mut self, channel: &mut C, pulse_width: u16) -> Result<(), Error<T::Error>> {
+        let ticks = self.microseconds_to_ticks(pulse_width as f32)?;
+        debug!("pulse width {} µs is {} ticks", pulse_width, ticks);
+
+        self.set_on_ticks(channel, ticks)
+    }
+
+    /// Sets `channel` to be on for the fraction `duty_cycle` (`0.0..1.0`) of every period.
+    /// `0.0` and `1.0` use the full off and full on bits.
+    pub fn set_duty_cycle<C: Channel>(&mut self, channel: &mut C, duty_cycle: f32) -> Result<(), Error<T::Error>> {
+        let fraction = Brightness::Fraction(duty_cycle).fraction()?;
+        let ticks = libm::roundf(fraction * constants::STEP_SIZE) as u16;
+
+        if ticks == 0 {
+            return self.set_full_off(channel);
+        }
+
+        self.set_on_ticks(channel, ticks)
+    }
+
+    /// Sets the LED on `channel` to `brightness`, given as a fraction, a percentage or a
+    /// 16-bit level. The lowest and highest brightness use the full off and full on bits.
+    pub fn set_brightness<B: Into<Brightness>>(&mut self, channel: &mut LedChannel, brightness: B) -> Result<(), Error<T::Error>> {
+        let ticks = channel.brightness_to_ticks(brightness)?;
+        debug!("brightness is {} ticks on channel {}", ticks, channel.channel_num());
+
+        if ticks == 0 {
+            return self.set_full_off(channel);
+        }
+
+        self.set_on_ticks(channel, ticks)
+    }
+
+    /// Reads the LED on `channel` back and returns its brightness as a fraction (`0.0..1.0`).
+    pub fn brightness(&mut self, channel: &LedChannel) -> Result<f32, Error<T::Error>> {
+        let state = self.get_channel(channel)?;
+        Ok(channel.ticks_to_brightness(state.on_ticks()))
+    }
+
+    /// Shows `color` on the colour LED `led`, writing all of its components in a single
+    /// I2C transaction. Returns `Error::IndexRange` without writing anything if a
+    /// component is on another board than the first, use a `ControllerChain` for those.
+    pub fn set_color<L: ColorLed, C: Into<L::Color>>(&mut self, led: &L, color: C) -> Result<(), Error<T::Error>> {
+        let outputs = led.outputs(color.into())?;
+        if outputs.as_slice().iter().any(|output| output.board != 0) {
+            return Err(Error::IndexRange(errors::IndexRangeError::new()));
+        }
+
+        self.write_components(outputs.as_slice().iter())
+    }
+
+    /// Writes the components of a colour LED as one `Batch`.
+    pub(crate) fn write_components<'a, I>(&mut self, outputs: I) -> Result<(), Error<T::Error>>
+        where I: Iterator<Item = &'a ComponentOutput>
+    {
+        let mut batch = Batch::new();
+        for output in outputs {
+            let channel = LedChannel::new(output.channel_num)?;
+            if output.ticks == 0 {
+                batch.set_full_off(&channel)?;
+            } else {
+                batch.set_channel(&channel, self.phase_offset.output(output.channel_num, output.ticks))?;
             }
         }
 
+        self.write_batch(&batch)
+    }
+
+    /// Turns `channel` fully on, a true 100% duty cycle.
+    pub fn set_full_on<C: Channel>(&mut self, channel: &mut C) -> Result<(), Error<T::Error>> {
+        self.set_channel(channel, Output::FullOn)
+    }
+
+    /// Turns `channel` fully off, a true 0% duty cycle.
+    pub fn set_full_off<C: Channel>(&mut self, channel: &mut C) -> Result<(), Error<T::Error>> {
+        self.set_channel(channel, Output::FullOff)
+    }
+
+    /// Moves the servo on `channel` to `angle` degrees, normalized on (-90...90),
+    /// using the pulse range from the channel's `ServoSettings`.
+    pub fn set_servo_angle(&mut self, channel: &mut ServoChannel, angle: f32) -> Result<(), Error<T::Error>> {
+        let pulse_width = channel.degrees_to_pulse_time(angle)?;
+        self.set_pulse_width(channel, pulse_width)
+    }
+
+    /// Claims channel `channel_num` as a servo with `settings`. The channel stays claimed
+    /// until the returned `ClaimedChannel` is dropped.
+    /// Returns `Error::ChannelInUse` if the channel has already been claimed.
+    #[cfg(feature = "std")]
+    pub fn take_servo(&self, channel_num: u8, settings: ServoSettings) -> Result<ClaimedChannel<ServoChannel>, Error<T::Error>> {
+        let channel = ServoChannel::new_with_settings(channel_num, settings)?;
+        self.claim(channel)
+    }
+
+    /// Claims channel `channel_num` as an LED. The channel stays claimed
+    /// until the returned `ClaimedChannel` is dropped.
+    /// Returns `Error::ChannelInUse` if the channel has already been claimed.
+    #[cfg(feature = "std")]
+    pub fn take_led(&self, channel_num: u8) -> Result<ClaimedChannel<LedChannel>, Error<T::Error>> {
+        let channel = LedChannel::new(channel_num)?;
+        self.claim(channel)
+    }
+
+    /// Returns the allocation mask of claimed channels, bit `n` is set while channel `n` is claimed.
+    #[cfg(feature = "std")]
+    pub fn claimed_channels(&self) -> u16 {
+        self.registry.mask()
+    }
+
+    /// Claims `channel` in the registry.
+    #[cfg(feature = "std")]
+    fn claim<C: Channel>(&self, channel: C) -> Result<ClaimedChannel<C>, Error<T::Error>> {
+        let channel_num = channel.channel_num();
+        if !self.registry.claim(channel_num) {
+            return Err(Error::ChannelInUse(channel_num));
+        }
+
+        debug!("claimed channel {}", channel_num);
+        Ok(self.registry.wrap(channel))
+    }
+
+    /// Reads `channel`'s registers back from the controller and decodes them.
+    pub fn get_channel<C: Channel>(&mut self, channel: &C) -> Result<ChannelState, Error<T::Error>> {
+        let data = if self.auto_increment {
+            channel.read_channel_block(&mut self.device)?
+        } else {
+            channel.read_channel(&mut self.device)?
+        };
+
+        Ok(ChannelState::new(channel.channel_num(), data, self.tick_length()))
+    }
+
+    /// Reads the state of all 16 channels. With auto-increment enabled this is
+    /// a single 64-byte I2C read. The result can be handed to `restore` later on.
+    pub fn snapshot(&mut self) -> Result<[ChannelState; 16], Error<T::Error>> {
+        let mut buf = [0u8; 4 * constants::NUM_CHANNELS as usize];
+
+        if self.auto_increment {
+            self.device.read_registers(constants::BASE_LED_ON_LOW, &mut buf).map_err(Error::Bus)?;
+        } else {
+            for (register, byte) in buf.iter_mut().enumerate() {
+                *byte = self.read_register(constants::BASE_LED_ON_LOW + register as u8)?;
+            }
+        }
+
+        let tick_length = self.tick_length();
+        let mut states = [ChannelState::default(); 16];
+        for (i, state) in states.iter_mut().enumerate() {
+            let mut data = [0u8; 4];
+            data.copy_from_slice(&buf[4 * i..4 * i + 4]);
+            *state = ChannelState::new(i as u8, data, tick_length);
+        }
+
+        Ok(states)
+    }
+
+    /// Writes previously read channel states back to the controller, including
+    /// their full on and full off bits. Consecutive channels are written in a single burst.
+    /// Returns `Error::IndexRange` without writing anything if any state has an invalid channel number.
+    pub fn restore(&mut self, states: &[ChannelState]) -> Result<(), Error<T::Error>> {
+        write_channel_bytes(&mut self.device, self.auto_increment, states, |state| (state.channel_num(), state.bytes()))
+    }
+
+    /// Writes every channel in `batch` in a single I2C transaction, so with `UpdateOn::Stop`
+    /// they all change at the same time. Channels between the lowest and highest one in the
+    /// batch are read first and written back unchanged.
+    ///
+    /// Without auto-increment the registers are written one at a time and do not latch together.
+    pub fn write_batch(&mut self, batch: &Batch) -> Result<(), Error<T::Error>> {
+        let (first, last) = match batch.span() {
+            Some(span) => span,
+            None => return Ok(()),
+        };
+
+        if !self.auto_increment {
+            let mut values = [(0u8, [0u8; 4]); constants::NUM_CHANNELS as usize];
+            let mut len = 0;
+            for channel_num in first..=last {
+                if let Some(output) = batch.output(channel_num) {
+                    values[len] = (channel_num, output.to_bytes());
+                    len += 1;
+                }
+            }
+
+            return write_channel_bytes(&mut self.device, false, &values[..len], |value| *value);
+        }
+
+        let mut buf = [0u8; 4 * constants::NUM_CHANNELS as usize];
+        let span = &mut buf[..4 * (last - first + 1) as usize];
+        let base = constants::BASE_LED_ON_LOW + 4 * first;
+
+        if batch.len() < (last - first + 1) as usize {
+            self.device.read_registers(base, span).map_err(Error::Bus)?;
+        }
+
+        for (i, data) in span.chunks_mut(4).enumerate() {
+            if let Some(output) = batch.output(first + i as u8) {
+                data.copy_from_slice(&output.to_bytes());
+            }
+        }
+
+        debug!("writing channels {}..={} in one transaction", first, last);
+        self.device.write_registers(base, span).map_err(Error::Bus)
+    }
+
+    /// Sets several channels at once. Each entry in `values` is a `(channel_num, on, off)` triple.
+    ///
+    /// When auto-increment is enabled, runs of consecutive channel numbers are written
+    /// as a single burst, so updating all 16 channels in order costs one 64-byte I2C write.
+    /// Returns `Error::IndexRange` without writing anything if any channel number is outside of `0..15`.
+    pub fn set_channels(&mut self, values: &[(u8, u16, u16)]) -> Result<(), Error<T::Error>> {
+        write_channel_bytes(&mut self.device, self.auto_increment, values, |&(channel_num, on, off)| (channel_num, Output::Pwm { on, off }.to_bytes()))
+    }
+
+    /// Sets every channel to drive `output` through the `ALL_LED_*` registers.
+    pub fn set_all_channels<O: Into<Output>>(&mut self, output: O) -> Result<(), Error<T::Error>> {
+        let data = output.into().to_bytes();
+
+        if self.auto_increment {
+            return self.device.write_registers(constants::ALL_LED_ON_LOW, &data).map_err(Error::Bus);
+        }
+
+        let registers = [
+            constants::ALL_LED_ON_LOW,
+            constants::ALL_LED_ON_HIGH,
+            constants::ALL_LED_OFF_LOW,
+            constants::ALL_LED_OFF_HIGH,
+        ];
+
+        for (register, value) in registers.iter().zip(data.iter()) {
+            self.write_register(*register, *value)?;
+        }
+
         Ok(())
     }
 
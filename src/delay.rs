@@ -0,0 +1,24 @@
+//! Delays used while the PCA9685 oscillator settles.
+//!
+//! `Controller` waits on the oscillator through an `embedded-hal` `DelayNs`
+//! implementation, so it can be driven by a hardware timer on targets without `std`.
+//! With the `std` feature enabled, `StdDelay` blocks the current thread instead.
+
+#[cfg(feature = "std")]
+use embedded_hal::delay::DelayNs;
+#[cfg(feature = "std")]
+use std::{thread, time};
+
+/// A `DelayNs` implementation that puts the current thread to sleep.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdDelay;
+
+#[cfg(feature = "std")]
+impl DelayNs for StdDelay {
+
+    fn delay_ns(&mut self, ns: u32) {
+        thread::sleep(time::Duration::from_nanos(ns as u64));
+    }
+
+}
@@ -0,0 +1,204 @@
+//! Register level access to a PCA9685 over different I2C backends.
+//!
+//! `Controller` and `Channel` are written against the `Device` trait, which is
+//! implemented for every `i2cdev` `I2CDevice` (with the `std` feature) and for `HalDevice`, a wrapper that
+//! pairs a blocking `embedded-hal` `I2c` bus with the controller's slave address.
+//! The same servo code therefore runs on a Linux SBC and on a microcontroller.
+//! On Linux, `LinuxDevice` does the same for an `i2c-dev` bus so it can be reset.
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+use core::fmt;
+#[cfg(all(feature = "std", target_os = "linux"))]
+use std::path::Path;
+
+use embedded_hal::i2c::{ I2c, Operation };
+#[cfg(feature = "std")]
+use i2cdev::core::I2CDevice;
+#[cfg(all(feature = "std", target_os = "linux"))]
+use i2cdev::linux::{ LinuxI2CDevice, LinuxI2CError };
+
+use crate::{
+    constants,
+    error::Error,
+    reset,
+};
+
+/// Register level access to a single PCA9685.
+pub trait Device {
+    /// Error type of the underlying bus.
+    type Error;
+
+    /// Reads a single register.
+    fn read_register(&mut self, register: u8) -> Result<u8, Self::Error>;
+
+    /// Writes a single register.
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), Self::Error>;
+
+    /// Fills `buf` from consecutive registers starting at `register`.
+    /// Requires auto-increment (`AI` on `MODE_1`) to be enabled on the controller.
+    fn read_registers(&mut self, register: u8, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Writes `values` to consecutive registers starting at `register` in a single I2C write.
+    /// Requires auto-increment (`AI` on `MODE_1`) to be enabled on the controller.
+    fn write_registers(&mut self, register: u8, values: &[u8]) -> Result<(), Self::Error>;
+}
+
+#[cfg(feature = "std")]
+impl<T: I2CDevice> Device for T {
+    type Error = T::Error;
+
+    fn read_register(&mut self, register: u8) -> Result<u8, T::Error> {
+        self.smbus_read_byte_data(register)
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), T::Error> {
+        self.smbus_write_byte_data(register, value)
+    }
+
+    fn read_registers(&mut self, register: u8, buf: &mut [u8]) -> Result<(), T::Error> {
+        self.smbus_write_byte(register)?;
+        self.read(buf)
+    }
+
+    fn write_registers(&mut self, register: u8, values: &[u8]) -> Result<(), T::Error> {
+        let mut data = Vec::with_capacity(values.len() + 1);
+        data.push(register);
+        data.extend_from_slice(values);
+
+        self.write(&data)
+    }
+}
+
+/// A PCA9685 on a blocking `embedded-hal` I2C bus.
+#[derive(Debug)]
+pub struct HalDevice<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C: I2c> HalDevice<I2C> {
+
+    /// Creates a `HalDevice` for the PCA9685 at `address` on `i2c`.
+    pub fn new(i2c: I2C, address: u8) -> HalDevice<I2C> {
+        HalDevice{
+            i2c,
+            address,
+        }
+    }
+
+    /// Creates a `HalDevice` for a PCA9685 at the default slave address (`0x40`).
+    pub fn with_default_address(i2c: I2C) -> HalDevice<I2C> {
+        HalDevice::new(i2c, constants::PCA9685_SLAVE_ADDRESS as u8)
+    }
+
+    /// Returns the slave address of the PCA9685.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Sends a software reset to every PCA9685 on the bus, see `reset::soft_reset_i2c`.
+    pub fn soft_reset(&mut self) -> Result<(), Error<I2C::Error>> {
+        reset::soft_reset_i2c(&mut self.i2c)
+    }
+
+    /// Releases the underlying I2C bus.
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+}
+
+impl<I2C: I2c> Device for HalDevice<I2C> {
+    type Error = I2C::Error;
+
+    fn read_register(&mut self, register: u8) -> Result<u8, I2C::Error> {
+        let mut buf = [0u8];
+        self.i2c.write_read(self.address, &[register], &mut buf)?;
+
+        Ok(buf[0])
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), I2C::Error> {
+        self.i2c.write(self.address, &[register, value])
+    }
+
+    fn read_registers(&mut self, register: u8, buf: &mut [u8]) -> Result<(), I2C::Error> {
+        self.i2c.write_read(self.address, &[register], buf)
+    }
+
+    fn write_registers(&mut self, register: u8, values: &[u8]) -> Result<(), I2C::Error> {
+        // Adjacent write operations are merged into a single I2C write
+        self.i2c.transaction(self.address, &mut [Operation::Write(&[register]), Operation::Write(values)])
+    }
+}
+
+/// A PCA9685 on a Linux `i2c-dev` bus. Unlike a bare `LinuxI2CDevice` it remembers the
+/// controller's slave address, so it can return there after a software reset.
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub struct LinuxDevice {
+    device: LinuxI2CDevice,
+    address: u16,
+}
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+impl LinuxDevice {
+
+    /// Opens the I2C bus at `path` (e.g. `/dev/i2c-1`) for the PCA9685 at `address`.
+    pub fn new<P: AsRef<Path>>(path: P, address: u16) -> Result<LinuxDevice, LinuxI2CError> {
+        Ok(
+            LinuxDevice{
+                device: LinuxI2CDevice::new(path, address)?,
+                address,
+            }
+        )
+    }
+
+    /// Returns the slave address of the PCA9685.
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// Sends a software reset to every PCA9685 on the bus and switches back to
+    /// this one, see `reset::soft_reset`.
+    pub fn soft_reset(&mut self) -> Result<(), Error<LinuxI2CError>> {
+        reset::soft_reset(&mut self.device, Some(self.address))
+    }
+
+    /// Releases the underlying `LinuxI2CDevice`.
+    pub fn into_inner(self) -> LinuxI2CDevice {
+        self.device
+    }
+
+}
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+impl fmt::Debug for LinuxDevice {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LinuxDevice")
+            .field("address", &self.address)
+            .finish_non_exhaustive()
+    }
+
+}
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+impl Device for LinuxDevice {
+    type Error = LinuxI2CError;
+
+    fn read_register(&mut self, register: u8) -> Result<u8, LinuxI2CError> {
+        self.device.read_register(register)
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+        self.device.write_register(register, value)
+    }
+
+    fn read_registers(&mut self, register: u8, buf: &mut [u8]) -> Result<(), LinuxI2CError> {
+        self.device.read_registers(register, buf)
+    }
+
+    fn write_registers(&mut self, register: u8, values: &[u8]) -> Result<(), LinuxI2CError> {
+        self.device.write_registers(register, values)
+    }
+}
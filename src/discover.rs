@@ -0,0 +1,162 @@
+//! Finding PCA9685s on an I2C bus.
+//!
+//! `discover` probes every address of a Linux I2C bus, `scan` does the same over an
+//! `embedded-hal` bus. A responding device is taken for a PCA9685 when its mode,
+//! subaddress and PRE_SCALE registers hold values the PCA9685 can hold; boards that
+//! still carry the power-on values are flagged as such. The LED All Call address and
+//! any subaddress a found board answers on are left out, as those are aliases of
+//! boards and not boards of their own.
+
+use embedded_hal::i2c::I2c;
+#[cfg(target_os = "linux")]
+use i2cdev::linux::{ LinuxI2CDevice, LinuxI2CError };
+#[cfg(target_os = "linux")]
+use std::path::Path;
+
+use crate::{
+    constants,
+    device::{ Device, HalDevice },
+    mode::{ Mode1, Mode2 },
+};
+
+/// Lowest and highest 7-bit addresses that are probed, the rest are reserved by the I2C specification.
+const FIRST_ADDRESS: u16 = 0x03;
+const LAST_ADDRESS: u16 = 0x77;
+
+/// Power-on values of `MODE_1`, `MODE_2` and `PRE_SCALE`.
+const RESET_MODE_1: u8 = 0x11;
+const RESET_MODE_2: u8 = 0x04;
+const RESET_PRE_SCALE: u8 = 0x1e;
+
+/// A device that looks like a PCA9685.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidate {
+    address: u16,
+    mode1: u8,
+    mode2: u8,
+    prescale: u8,
+    shared: [u8; 4],
+}
+
+impl Candidate {
+
+    /// Returns the I2C slave address the device answered on.
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// Returns the `MODE_1` flags that were read.
+    pub fn mode1(&self) -> Mode1 {
+        Mode1::from_bits_truncate(self.mode1)
+    }
+
+    /// Returns the `MODE_2` flags that were read.
+    pub fn mode2(&self) -> Mode2 {
+        Mode2::from_bits_truncate(self.mode2)
+    }
+
+    /// Returns the PRE_SCALE value that was read.
+    pub fn prescale(&self) -> u8 {
+        self.prescale
+    }
+
+    /// Returns whether `MODE_1`, `MODE_2` and `PRE_SCALE` hold their power-on values,
+    /// which makes a PCA9685 very likely. Boards that have already been set up won't.
+    pub fn at_reset_defaults(&self) -> bool {
+        self.mode1 == RESET_MODE_1 && self.mode2 == RESET_MODE_2 && self.prescale == RESET_PRE_SCALE
+    }
+
+    /// Returns the LED All Call address and subaddresses the device currently answers on.
+    pub fn shared_addresses(&self) -> Vec<u16> {
+        let flags = [Mode1::ALLCALL, Mode1::SUB1, Mode1::SUB2, Mode1::SUB3];
+        let registers = [
+            constants::ALL_CALL_ADDR,
+            constants::SUBADDR_1,
+            constants::SUBADDR_2,
+            constants::SUBADDR_3,
+        ];
+
+        flags.iter().zip(registers.iter())
+            .filter(|&(flag, _)| self.mode1().contains(*flag))
+            .map(|(_, register)| (self.shared[(register - constants::SUBADDR_1) as usize] >> 1) as u16)
+            .collect()
+    }
+
+}
+
+/// Reads the identifying registers of the device behind `device`. Returns `None`
+/// if it does not respond or holds values a PCA9685 can't.
+fn identify<T: Device>(address: u16, device: &mut T) -> Option<Candidate> {
+    let mode1 = device.read_register(constants::MODE_1).ok()?;
+    debug!("{:#04x} responded, MODE_1 {:#04x}", address, mode1);
+
+    let mode2 = device.read_register(constants::MODE_2).ok()?;
+    let prescale = device.read_register(constants::PRE_SCALE).ok()?;
+
+    let mut shared = [0u8; 4];
+    for (offset, value) in shared.iter_mut().enumerate() {
+        *value = device.read_register(constants::SUBADDR_1 + offset as u8).ok()?;
+    }
+
+    // The top three bits of `MODE_2` and the lowest bit of every address register are
+    // reserved and read back as zero, PRE_SCALE is clamped to at least 3 by the controller
+    let plausible = mode2 & 0xe0 == 0
+        && prescale >= constants::PRE_SCALE_MIN
+        && shared.iter().all(|value| value & 0x01 == 0);
+
+    if !plausible {
+        debug!("{:#04x} does not look like a PCA9685", address);
+        return None;
+    }
+
+    Some(Candidate{
+        address,
+        mode1,
+        mode2,
+        prescale,
+        shared,
+    })
+}
+
+/// Drops candidates that are the All Call address or an enabled subaddress of another candidate.
+fn without_shared(candidates: Vec<Candidate>) -> Vec<Candidate> {
+    let mut shared: Vec<u16> = candidates.iter()
+        .flat_map(|candidate| candidate.shared_addresses())
+        .collect();
+    shared.push(constants::ALL_CALL_ADDRESS);
+
+    candidates.into_iter()
+        .filter(|candidate| !shared.contains(&candidate.address))
+        .collect()
+}
+
+/// Probes every address on an `embedded-hal` bus and returns the devices that look like PCA9685s.
+pub fn scan<I: I2c>(i2c: &mut I) -> Vec<Candidate> {
+    let candidates = (FIRST_ADDRESS..=LAST_ADDRESS)
+        .filter_map(|address| identify(address, &mut HalDevice::new(&mut *i2c, address as u8)))
+        .collect();
+
+    without_shared(candidates)
+}
+
+/// Probes every address on the Linux I2C bus at `path` (for example `/dev/i2c-1`)
+/// and returns the devices that look like PCA9685s.
+/// Returns an error if the bus can't be opened.
+#[cfg(target_os = "linux")]
+pub fn discover<P: AsRef<Path>>(path: P) -> Result<Vec<Candidate>, LinuxI2CError> {
+    let mut device = LinuxI2CDevice::new(path, FIRST_ADDRESS)?;
+    let mut candidates = Vec::new();
+
+    for address in FIRST_ADDRESS..=LAST_ADDRESS {
+        if device.set_slave_address(address).is_err() {
+            debug!("{:#04x} is busy, skipping", address);
+            continue;
+        }
+
+        if let Some(candidate) = identify(address, &mut device) {
+            candidates.push(candidate);
+        }
+    }
+
+    Ok(without_shared(candidates))
+}
@@ -0,0 +1,79 @@
+//! The error type shared by every fallible operation that touches the bus.
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::channel::errors::{ IndexRangeError, ValueRangeError };
+
+/// Errors returned by this crate, where `E` is the error type of the underlying I2C device.
+///
+/// Operations that never touch the bus (such as `LedChannel::new` or
+/// `ServoChannel::degrees_to_pulse_time`) return the narrower `IndexRangeError` or
+/// `ValueRangeError` directly; both convert into `Error` through `?`.
+#[derive(Debug)]
+pub enum Error<E> {
+    /// The underlying I2C device returned an error.
+    Bus(E),
+    /// A channel index was outside of `0..15`.
+    IndexRange(IndexRangeError),
+    /// A value, such as a pulse width or servo angle, was out of range.
+    ValueRange(ValueRangeError),
+    /// A PWM or oscillator frequency cannot be used by the controller.
+    InvalidFrequency(ValueRangeError),
+    /// The controller did not come out of set up in the expected state.
+    NotInitialized,
+    /// The channel with this index has already been claimed from the controller.
+    ChannelInUse(u8),
+    /// A board with this I2C slave address is already part of the chain.
+    DuplicateAddress(u16),
+}
+
+impl<E> From<IndexRangeError> for Error<E> {
+
+    fn from(err: IndexRangeError) -> Error<E> {
+        Error::IndexRange(err)
+    }
+
+}
+
+impl<E> From<ValueRangeError> for Error<E> {
+
+    fn from(err: ValueRangeError) -> Error<E> {
+        Error::ValueRange(err)
+    }
+
+}
+
+#[cfg(feature = "std")]
+impl<E: error::Error + 'static> error::Error for Error<E> {
+
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Bus(err) => Some(err),
+            Error::IndexRange(err) => Some(err),
+            Error::ValueRange(err) => Some(err),
+            Error::InvalidFrequency(err) => Some(err),
+            Error::NotInitialized => None,
+            Error::ChannelInUse(_) => None,
+            Error::DuplicateAddress(_) => None,
+        }
+    }
+
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Bus(err) => write!(f, "i2c bus error: {}", err),
+            Error::IndexRange(err) => write!(f, "{}", err),
+            Error::ValueRange(err) => write!(f, "{}", err),
+            Error::InvalidFrequency(err) => write!(f, "invalid frequency: {}", err),
+            Error::NotInitialized => write!(f, "controller is not initialized"),
+            Error::ChannelInUse(channel_num) => write!(f, "channel {} is already claimed", channel_num),
+            Error::DuplicateAddress(address) => write!(f, "a board at address {:#04x} is already chained", address),
+        }
+    }
+
+}
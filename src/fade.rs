@@ -0,0 +1,382 @@
+//! Timed brightness fades for LED channels.
+//!
+//! A `Fader` ramps channels from their current brightness to a target over a
+//! duration, shaped by an `Easing` function. It has no clock of its own: every
+//! call to `Fader::tick` passes the current time, measured from any fixed point,
+//! and writes the brightness each fading channel should have at that moment.
+//! With the `std` feature, `BackgroundFader` ticks a `SharedController` from a
+//! thread instead.
+
+use core::fmt;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::error;
+#[cfg(feature = "std")]
+use std::sync::{ Arc, Mutex, MutexGuard, PoisonError };
+#[cfg(feature = "std")]
+use std::sync::atomic::{ AtomicBool, Ordering };
+#[cfg(feature = "std")]
+use std::thread::{ self, JoinHandle };
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use embedded_hal::delay::DelayNs;
+
+use crate::channel::{
+    base::Channel,
+    errors,
+    led::{ Brightness, LedChannel },
+};
+use crate::constants;
+use crate::controller::Controller;
+use crate::device::Device;
+use crate::error::Error;
+use crate::rgb::ComponentOutput;
+#[cfg(feature = "std")]
+use crate::shared::ControllerHandle;
+
+/// Shape of a fade over time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    /// Constant speed.
+    #[default]
+    Linear,
+    /// Starts slowly and speeds up, quadratic.
+    EaseIn,
+    /// Starts quickly and slows down, quadratic.
+    EaseOut,
+    /// Starts and ends slowly, quadratic.
+    EaseInOut,
+    /// Starts slowly and speeds up, cubic.
+    CubicIn,
+    /// Starts quickly and slows down, cubic.
+    CubicOut,
+    /// Starts and ends slowly, cubic.
+    CubicInOut,
+}
+
+impl Easing {
+
+    /// Maps `progress` through the fade (`0.0..1.0`) onto how far the
+    /// brightness has moved towards its target (`0.0..1.0`).
+    ///
+    /// ```
+    /// # extern crate rust_pca9685;
+    /// # use rust_pca9685::fade::Easing;
+    /// #
+    /// assert_eq!(0.25, Easing::EaseIn.apply(0.5));
+    /// assert_eq!(0.5, Easing::CubicInOut.apply(0.5));
+    /// assert_eq!(1.0, Easing::CubicOut.apply(1.5));
+    /// ```
+    pub fn apply(&self, progress: f32) -> f32 {
+        let t = progress.clamp(0.0, 1.0);
+
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - 2.0 * (1.0 - t) * (1.0 - t)
+                }
+            },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t) * (1.0 - t) * (1.0 - t),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - 4.0 * (1.0 - t) * (1.0 - t) * (1.0 - t)
+                }
+            },
+        }
+    }
+
+}
+
+/// Error returned when a fade cannot be started.
+#[derive(Clone, Debug)]
+pub enum FadeError {
+    /// The channel is not one of the controller's 16 channels.
+    IndexRange(errors::IndexRangeError),
+    /// The target brightness is out of range.
+    ValueRange(errors::ValueRangeError),
+}
+
+impl From<errors::IndexRangeError> for FadeError {
+
+    fn from(err: errors::IndexRangeError) -> FadeError {
+        FadeError::IndexRange(err)
+    }
+
+}
+
+impl From<errors::ValueRangeError> for FadeError {
+
+    fn from(err: errors::ValueRangeError) -> FadeError {
+        FadeError::ValueRange(err)
+    }
+
+}
+
+impl<E> From<FadeError> for Error<E> {
+
+    fn from(err: FadeError) -> Error<E> {
+        match err {
+            FadeError::IndexRange(err) => Error::IndexRange(err),
+            FadeError::ValueRange(err) => Error::ValueRange(err),
+        }
+    }
+
+}
+
+#[cfg(feature = "std")]
+impl error::Error for FadeError {
+
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            FadeError::IndexRange(err) => Some(err),
+            FadeError::ValueRange(err) => Some(err),
+        }
+    }
+
+}
+
+impl fmt::Display for FadeError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FadeError::IndexRange(err) => write!(f, "{}", err),
+            FadeError::ValueRange(err) => write!(f, "{}", err),
+        }
+    }
+
+}
+
+/// A fade of a single channel. The starting point is taken on the first tick.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Fade {
+    channel: LedChannel,
+    target: f32,
+    duration: Duration,
+    easing: Easing,
+    start: Option<(Duration, f32)>,
+}
+
+/// Fades channels of one controller, one fade per channel.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fader {
+    fades: [Option<Fade>; constants::NUM_CHANNELS as usize],
+}
+
+impl Fader {
+
+    /// Creates a `Fader` without any fades.
+    pub fn new() -> Fader {
+        Fader::default()
+    }
+
+    /// Fades `channel` from its current brightness to `target` over `duration`, starting on
+    /// the next tick. The channel's brightness curve is applied along the way.
+    /// Replaces a fade that is already running on the channel.
+    pub fn fade<B: Into<Brightness>>(&mut self, channel: LedChannel, target: B, duration: Duration, easing: Easing) -> Result<(), FadeError> {
+        let target = target.into().fraction()?;
+        let slot = self.fades.get_mut(channel.channel_num() as usize).ok_or(errors::IndexRangeError)?;
+
+        *slot = Some(Fade{
+            channel,
+            target,
+            duration,
+            easing,
+            start: None,
+        });
+
+        Ok(())
+    }
+
+    /// Stops the fade on `channel`, leaving it at the brightness of the last tick.
+    /// Returns whether a fade was running.
+    pub fn cancel<C: Channel>(&mut self, channel: &C) -> bool {
+        match self.fades.get_mut(channel.channel_num() as usize) {
+            Some(slot) => slot.take().is_some(),
+            None => false,
+        }
+    }
+
+    /// Stops every fade.
+    pub fn cancel_all(&mut self) {
+        self.fades = [None; constants::NUM_CHANNELS as usize];
+    }
+
+    /// Returns whether `channel` is fading.
+    pub fn is_fading<C: Channel>(&self, channel: &C) -> bool {
+        matches!(self.fades.get(channel.channel_num() as usize), Some(Some(_)))
+    }
+
+    /// Returns whether any channel is fading.
+    pub fn is_active(&self) -> bool {
+        self.fades.iter().any(|fade| fade.is_some())
+    }
+
+    /// Writes the brightness every fading channel should have at `now`, in a single batch.
+    /// Fades that have reached their target are removed once it has been written.
+    /// Returns whether any fade is still running.
+    pub fn tick<T: Device, D: DelayNs>(&mut self, controller: &mut Controller<T, D>, now: Duration) -> Result<bool, Error<T::Error>> {
+        let mut outputs = [ComponentOutput::default(); constants::NUM_CHANNELS as usize];
+        let mut len = 0;
+        let mut finished = [false; constants::NUM_CHANNELS as usize];
+
+        for (index, slot) in self.fades.iter_mut().enumerate() {
+            let fade = match slot {
+                Some(fade) => fade,
+                None => continue,
+            };
+
+            let (started, from) = match fade.start {
+                Some(start) => start,
+                None => {
+                    let start = (now, controller.brightness(&fade.channel)?);
+                    fade.start = Some(start);
+                    start
+                },
+            };
+
+            let elapsed = now.checked_sub(started).unwrap_or_default();
+            let progress = if fade.duration.as_nanos() == 0 {
+                1.0
+            } else {
+                (elapsed.as_secs_f32() / fade.duration.as_secs_f32()).min(1.0)
+            };
+
+            let brightness = from + (fade.target - from) * fade.easing.apply(progress);
+            outputs[len] = ComponentOutput{
+                board: 0,
+                channel_num: fade.channel.channel_num(),
+                ticks: fade.channel.brightness_to_ticks(brightness.clamp(0.0, 1.0))?,
+            };
+            len += 1;
+
+            finished[index] = progress >= 1.0;
+        }
+
+        if len > 0 {
+            controller.write_components(outputs[..len].iter())?;
+        }
+
+        // Finished fades are only dropped once their last step is written, so a failed tick is retried
+        for (slot, finished) in self.fades.iter_mut().zip(finished.iter()) {
+            if *finished {
+                *slot = None;
+            }
+        }
+
+        Ok(self.is_active())
+    }
+
+    /// Takes over the fades of `ticked`, a ticked copy of `before`, for every channel
+    /// whose fade is still the one in `before`. Fades started or cancelled meanwhile are kept.
+    #[cfg(feature = "std")]
+    fn merge(&mut self, before: &Fader, ticked: &Fader) {
+        let slots = self.fades.iter_mut().zip(before.fades.iter().zip(ticked.fades.iter()));
+        for (slot, (before, ticked)) in slots {
+            if slot == before {
+                *slot = *ticked;
+            }
+        }
+    }
+
+}
+
+/// A `Fader` ticked from a background thread, driving a `SharedController`.
+/// The thread is stopped when the `BackgroundFader` is dropped.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct BackgroundFader {
+    fader: Arc<Mutex<Fader>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[cfg(feature = "std")]
+impl BackgroundFader {
+
+    /// Starts a thread that ticks the controller behind `handle` every `interval`.
+    /// Errors writing to the controller are logged and the fades carry on.
+    /// The thread ticks a copy of the `Fader`, so it never holds the `Fader` and
+    /// the controller locks at the same time.
+    pub fn spawn<T, D>(handle: ControllerHandle<T, D>, interval: Duration) -> BackgroundFader
+        where T: Device + Send + 'static, T::Error: core::fmt::Debug, D: DelayNs + Send + 'static
+    {
+        let fader = Arc::new(Mutex::new(Fader::new()));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread = {
+            let fader = fader.clone();
+            let running = running.clone();
+            thread::spawn(move || {
+                let epoch = Instant::now();
+                while running.load(Ordering::SeqCst) {
+                    let before = *fader.lock().unwrap_or_else(PoisonError::into_inner);
+                    if before.is_active() {
+                        let mut ticked = before;
+                        if let Err(err) = handle.with(|controller| ticked.tick(controller, epoch.elapsed())) {
+                            warn!("fade tick failed: {:?}", err);
+                        }
+
+                        fader.lock().unwrap_or_else(PoisonError::into_inner).merge(&before, &ticked);
+                    }
+
+                    thread::sleep(interval);
+                }
+            })
+        };
+
+        BackgroundFader{
+            fader,
+            running,
+            thread: Some(thread),
+        }
+    }
+
+    /// Locks the `Fader` to start, cancel or inspect fades.
+    pub fn lock(&self) -> MutexGuard<'_, Fader> {
+        self.fader.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Fades `channel` to `target` over `duration`, see `Fader::fade`.
+    pub fn fade<B: Into<Brightness>>(&self, channel: LedChannel, target: B, duration: Duration, easing: Easing) -> Result<(), FadeError> {
+        self.lock().fade(channel, target, duration, easing)
+    }
+
+    /// Stops the fade on `channel`, see `Fader::cancel`.
+    pub fn cancel<C: Channel>(&self, channel: &C) -> bool {
+        self.lock().cancel(channel)
+    }
+
+    /// Returns whether any channel is fading.
+    pub fn is_active(&self) -> bool {
+        self.lock().is_active()
+    }
+
+    /// Stops the thread and waits for it to exit. Running fades stay where they are.
+    pub fn stop(mut self) {
+        self.shut_down();
+    }
+
+    fn shut_down(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+}
+
+#[cfg(feature = "std")]
+impl Drop for BackgroundFader {
+    fn drop(&mut self) {
+        self.shut_down();
+    }
+}
@@ -0,0 +1,75 @@
+//! Synchronised writes to several PCA9685s through a shared I2C address.
+//!
+//! Every PCA9685 with `ALLCALL` set answers on the LED All Call address (`0x70` at
+//! power-on), and each can additionally answer on up to three programmable
+//! subaddresses, see `Controller::set_subaddress`. A `Group` writes channel registers
+//! to one of those addresses, so all member boards latch the same values from a
+//! single bus transaction. The shared addresses are write-only.
+
+use embedded_hal::i2c::I2c;
+
+use crate::{
+    constants,
+    channel::{
+        base::Channel,
+        output::Output,
+    },
+    controller::write_channel_bytes,
+    device::{ Device, HalDevice },
+    error::Error,
+};
+
+/// Channel writes to every PCA9685 answering on the address `device` talks to.
+/// Members must have auto-increment enabled, which `Controller::new` does.
+#[derive(Debug)]
+pub struct Group<T: Device> {
+    device: T,
+}
+
+impl<I2C: I2c> Group<HalDevice<I2C>> {
+
+    /// Creates a `Group` that writes to the LED All Call address on `i2c`.
+    pub fn all_call(i2c: I2C) -> Group<HalDevice<I2C>> {
+        Group::new(HalDevice::new(i2c, constants::ALL_CALL_ADDRESS as u8))
+    }
+
+    /// Creates a `Group` that writes to `address` on `i2c`, which should be a
+    /// subaddress or All Call address programmed on the member boards.
+    pub fn at(i2c: I2C, address: u8) -> Group<HalDevice<I2C>> {
+        Group::new(HalDevice::new(i2c, address))
+    }
+
+}
+
+impl<T: Device> Group<T> {
+
+    /// Creates a `Group` from a `device` that is addressed at a subaddress or the All Call address.
+    pub fn new(device: T) -> Group<T> {
+        Group{
+            device,
+        }
+    }
+
+    /// Consumes the `Group` and returns its device.
+    pub fn into_inner(self) -> T {
+        self.device
+    }
+
+    /// Sets `channel` on every member board to drive `output`, in a single I2C write.
+    pub fn set_channel<C: Channel, O: Into<Output>>(&mut self, channel: &mut C, output: O) -> Result<(), Error<T::Error>> {
+        channel.write_channel_block(&mut self.device, output.into().to_bytes())
+    }
+
+    /// Sets several channels on every member board, see `Controller::set_channels`.
+    /// Runs of consecutive channels are written as a single burst.
+    pub fn set_channels(&mut self, values: &[(u8, u16, u16)]) -> Result<(), Error<T::Error>> {
+        write_channel_bytes(&mut self.device, true, values, |&(channel_num, on, off)| (channel_num, Output::Pwm { on, off }.to_bytes()))
+    }
+
+    /// Sets every channel on every member board to drive `output`.
+    pub fn set_all_channels<O: Into<Output>>(&mut self, output: O) -> Result<(), Error<T::Error>> {
+        let data = output.into().to_bytes();
+        self.device.write_registers(constants::ALL_LED_ON_LOW, &data).map_err(Error::Bus)
+    }
+
+}
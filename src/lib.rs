@@ -7,17 +7,47 @@
 //! This library references the following resources:
 //! - [Adafruit Python PCA9685](https://github.com/adafruit/Adafruit_Python_PCA9685)
 //! - [PCA9685 Datasheet](https://cdn-shop.adafruit.com/datasheets/PCA9685.pdf)
+//!
+//! The library is `no_std` and does not allocate when built without the default
+//! `std` feature. The `std` feature adds the `i2cdev` backend and `StdDelay`, and
+//! the `async` feature adds `AsyncController` for `embedded-hal-async` buses.
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(missing_debug_implementations)]
 
+#[macro_use]
+extern crate bitflags;
+extern crate embedded_hal;
+#[cfg(feature = "async")]
+extern crate embedded_hal_async;
+#[cfg(feature = "std")]
 extern crate i2cdev;
+extern crate libm;
 
 #[macro_use]
 extern crate log;
+#[cfg(feature = "std")]
 extern crate env_logger;
 
+#[cfg(feature = "async")]
+pub mod async_controller;
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod chain;
 pub mod constants;
 pub mod controller;
 pub mod channel;
-#[cfg(target_os = "linux")]
-pub mod reset;
\ No newline at end of file
+pub mod delay;
+pub mod device;
+#[cfg(feature = "std")]
+pub mod discover;
+pub mod error;
+pub mod fade;
+pub mod group;
+pub mod mode;
+pub mod reset;
+pub mod rgb;
+#[cfg(feature = "std")]
+pub mod shared;
+
+pub use error::Error;
\ No newline at end of file
@@ -0,0 +1,199 @@
+//! Typed views of the PCA9685 `MODE_1` and `MODE_2` registers.
+//!
+//! Each register is represented as a set of bitflags, so controller
+//! configuration can be inspected and changed without hand-rolled masks.
+//! Bit positions are taken from the
+//! [PCA9685 datasheet](https://cdn-shop.adafruit.com/datasheets/PCA9685.pdf), section 7.3.1.
+
+use crate::constants;
+
+bitflags! {
+    /// Flags stored in the `MODE_1` register.
+    pub struct Mode1: u8 {
+        /// Restart is enabled. Writing this bit back as `1` clears it and
+        /// restarts all channels that were running before `SLEEP`.
+        const RESTART = constants::RESTART;
+        /// Use the EXTCLK pin as the clock source.
+        const EXTCLK = constants::EXTCLK;
+        /// Register auto-increment is enabled.
+        const AI = constants::AI;
+        /// Low power mode, oscillator off.
+        const SLEEP = constants::SLEEP;
+        /// Respond to I2C bus subaddress 1.
+        const SUB1 = constants::SUB1;
+        /// Respond to I2C bus subaddress 2.
+        const SUB2 = constants::SUB2;
+        /// Respond to I2C bus subaddress 3.
+        const SUB3 = constants::SUB3;
+        /// Respond to the LED All Call I2C bus address.
+        const ALLCALL = constants::ALL_CALL;
+    }
+}
+
+bitflags! {
+    /// Flags stored in the `MODE_2` register.
+    pub struct Mode2: u8 {
+        /// Output logic state is inverted.
+        const INVRT = constants::INVRT;
+        /// Outputs change on ACK instead of on STOP.
+        const OCH = constants::OUTPUT_CHANGE;
+        /// Outputs are configured with a totem pole structure instead of open-drain.
+        const OUTDRV = constants::OUTDRV;
+        /// High bit of the `OUTNE` field.
+        const OUTNE1 = constants::OUTNE_1;
+        /// Low bit of the `OUTNE` field.
+        const OUTNE0 = constants::OUTNE_0;
+        /// Both bits of the `OUTNE` field, which controls the outputs when /OE = 1.
+        const OUTNE = constants::OUTNE_1 | constants::OUTNE_0;
+    }
+}
+
+/// When new channel values take effect, the `OCH` flag on `MODE_2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateOn {
+    /// Outputs change on the I2C STOP condition, so every register written
+    /// in one transaction latches at the same time.
+    Stop,
+    /// Outputs change on the ACK of each register write.
+    Ack,
+}
+
+/// Structure of the output drivers, `OUTDRV` on `MODE_2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputDriver {
+    /// Outputs drive both high and low, e.g. servo breakouts and external drivers.
+    TotemPole,
+    /// Outputs only sink current, e.g. LEDs connected directly between V+ and the pin.
+    OpenDrain,
+}
+
+/// State of the outputs while the /OE pin is high, the `OUTNE` field on `MODE_2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputDisabled {
+    /// Outputs are driven to 0.
+    Low,
+    /// Outputs are driven to 1 with `OutputDriver::TotemPole`, and
+    /// high-impedance with `OutputDriver::OpenDrain`.
+    High,
+    /// Outputs are high-impedance.
+    HighImpedance,
+}
+
+/// Output driver configuration of a controller, stored in `MODE_2`.
+///
+/// Defaults to totem-pole outputs that are not inverted and go low while /OE is high.
+///
+/// ```
+/// # extern crate rust_pca9685;
+/// # use rust_pca9685::mode::{ Mode2, OutputConfig, OutputDisabled };
+/// #
+/// // External N-channel MOSFETs on open-drain outputs
+/// let config = OutputConfig::new()
+///     .open_drain()
+///     .inverted(true)
+///     .when_disabled(OutputDisabled::HighImpedance);
+/// assert_eq!(Mode2::INVRT | Mode2::OUTNE1, config.mode2());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutputConfig {
+    driver: OutputDriver,
+    inverted: bool,
+    disabled: OutputDisabled,
+}
+
+impl OutputConfig {
+
+    /// Creates an `OutputConfig` with the default totem-pole configuration.
+    pub fn new() -> OutputConfig {
+        OutputConfig{
+            driver: OutputDriver::TotemPole,
+            inverted: false,
+            disabled: OutputDisabled::Low,
+        }
+    }
+
+    /// Reads an `OutputConfig` back from the `MODE_2` flags in `mode`.
+    pub fn from_mode2(mode: Mode2) -> OutputConfig {
+        let disabled = if mode.contains(Mode2::OUTNE1) {
+            OutputDisabled::HighImpedance
+        } else if mode.contains(Mode2::OUTNE0) {
+            OutputDisabled::High
+        } else {
+            OutputDisabled::Low
+        };
+
+        OutputConfig{
+            driver: if mode.contains(Mode2::OUTDRV) { OutputDriver::TotemPole } else { OutputDriver::OpenDrain },
+            inverted: mode.contains(Mode2::INVRT),
+            disabled,
+        }
+    }
+
+    /// Sets the structure of the output drivers.
+    pub fn driver(mut self, driver: OutputDriver) -> OutputConfig {
+        self.driver = driver;
+        self
+    }
+
+    /// Uses totem-pole outputs.
+    pub fn totem_pole(self) -> OutputConfig {
+        self.driver(OutputDriver::TotemPole)
+    }
+
+    /// Uses open-drain outputs.
+    pub fn open_drain(self) -> OutputConfig {
+        self.driver(OutputDriver::OpenDrain)
+    }
+
+    /// Inverts the output logic state, for outputs that drive external N-channel drivers.
+    pub fn inverted(mut self, inverted: bool) -> OutputConfig {
+        self.inverted = inverted;
+        self
+    }
+
+    /// Sets the state of the outputs while /OE is high.
+    pub fn when_disabled(mut self, disabled: OutputDisabled) -> OutputConfig {
+        self.disabled = disabled;
+        self
+    }
+
+    /// Returns the structure of the output drivers.
+    pub fn output_driver(&self) -> OutputDriver {
+        self.driver
+    }
+
+    /// Returns whether the output logic state is inverted.
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Returns the state of the outputs while /OE is high.
+    pub fn disabled_state(&self) -> OutputDisabled {
+        self.disabled
+    }
+
+    /// Returns the `MODE_2` flags for this configuration.
+    pub fn mode2(&self) -> Mode2 {
+        let mut mode = match self.disabled {
+            OutputDisabled::Low => Mode2::empty(),
+            OutputDisabled::High => Mode2::OUTNE0,
+            OutputDisabled::HighImpedance => Mode2::OUTNE1,
+        };
+
+        if self.driver == OutputDriver::TotemPole {
+            mode |= Mode2::OUTDRV;
+        }
+        if self.inverted {
+            mode |= Mode2::INVRT;
+        }
+
+        mode
+    }
+
+}
+
+impl Default for OutputConfig {
+    fn default() -> OutputConfig {
+        OutputConfig::new()
+    }
+}
@@ -1,25 +1,85 @@
+//! Software reset (`SWRST`) of the PCA9685s on a bus.
+//!
+//! A software reset is the General Call address (`0x00`) followed by the `SWRST`
+//! byte (`0x06`), and returns every PCA9685 on the bus to its power-on state.
+//! `SoftReset` is implemented by devices that can reach the General Call address
+//! on their own and know where to return to, `HalDevice` and `LinuxDevice`.
+//! Other `i2cdev` devices get there with `soft_reset`, which switches their slave
+//! address through `SetSlaveAddress` and back again.
+
+use embedded_hal::i2c::I2c;
+#[cfg(feature = "std")]
 use env_logger;
+#[cfg(feature = "std")]
 use i2cdev::core::I2CDevice;
-use i2cdev::linux::*;
+#[cfg(all(feature = "std", target_os = "linux"))]
+use i2cdev::linux::{ LinuxI2CDevice, LinuxI2CError };
+
+use crate::{
+    constants,
+    device::{ Device, HalDevice },
+    error::Error,
+};
+#[cfg(all(feature = "std", target_os = "linux"))]
+use crate::device::LinuxDevice;
+
+/// A `Device` that can send a software reset to the bus it sits on.
+pub trait SoftReset: Device {
+    /// Writes `SWRST` to the General Call address, resetting every PCA9685 on the bus.
+    fn soft_reset(&mut self) -> Result<(), Error<Self::Error>>;
+}
 
-use ::constants;
+/// An `i2cdev` device whose slave address can be changed after it has been opened.
+#[cfg(feature = "std")]
+pub trait SetSlaveAddress: I2CDevice {
+    /// Points the device at `slave_address` for all following transfers.
+    fn set_slave_address(&mut self, slave_address: u16) -> Result<(), Self::Error>;
+}
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+impl SetSlaveAddress for LinuxI2CDevice {
+    fn set_slave_address(&mut self, slave_address: u16) -> Result<(), LinuxI2CError> {
+        LinuxI2CDevice::set_slave_address(self, slave_address)
+    }
+}
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+impl SoftReset for LinuxDevice {
+    fn soft_reset(&mut self) -> Result<(), Error<LinuxI2CError>> {
+        LinuxDevice::soft_reset(self)
+    }
+}
+
+impl<I2C: I2c> SoftReset for HalDevice<I2C> {
+    fn soft_reset(&mut self) -> Result<(), Error<I2C::Error>> {
+        HalDevice::soft_reset(self)
+    }
+}
 
 /// Writes a SOFT_RESET directly to the I2C master and switches the device back to the PCA9685 slave.
-pub fn soft_reset(dev: &mut LinuxI2CDevice, slave_addr: Option<u16>) -> Result<(), LinuxI2CError> {
+#[cfg(feature = "std")]
+pub fn soft_reset<T: SetSlaveAddress>(dev: &mut T, slave_addr: Option<u16>) -> Result<(), Error<T::Error>> {
     let _ = env_logger::try_init();
 
     let slave_addr = slave_addr.unwrap_or(constants::PCA9685_SLAVE_ADDRESS);
 
-    /// Switch slave address to the I2C master address
+    // Switch slave address to the I2C master address
     debug!("switching to master communication, sending soft reset");
-    dev.set_slave_address(constants::I2C_MASTER_ADDRESS);
+    dev.set_slave_address(constants::I2C_MASTER_ADDRESS).map_err(Error::Bus)?;
 
-    /// Get the result from writing SOFT_RESET to master
+    // Get the result from writing SOFT_RESET to master
     let result = dev.smbus_write_byte(constants::SOFT_RESET);
 
-    /// Revert to requested slave address after reset
+    // Revert to requested slave address after reset
     debug!("reverting to slave address {:#x}", slave_addr);
-    dev.set_slave_address(slave_addr);
+    dev.set_slave_address(slave_addr).map_err(Error::Bus)?;
+
+    result.map_err(Error::Bus)
+}
 
-    return result;
+/// Writes a SOFT_RESET to the I2C master address of an `embedded-hal` bus.
+/// Every PCA9685 on the bus returns to its power-on state.
+pub fn soft_reset_i2c<I: I2c>(i2c: &mut I) -> Result<(), Error<I::Error>> {
+    debug!("sending soft reset to the i2c master address");
+    i2c.write(constants::I2C_MASTER_ADDRESS as u8, &[constants::SOFT_RESET]).map_err(Error::Bus)
 }
@@ -0,0 +1,368 @@
+//! RGB and RGBW LEDs wired across several channels.
+//!
+//! An `RgbLed` binds three `LedChannel`s and an `RgbwLed` four, each component
+//! optionally sitting on a different board of a `ControllerChain`. Colours are given
+//! as `Rgb` (from components, HSV or a hex string) or `Rgbw`, scaled by the LED's
+//! white balance, passed through each channel's brightness curve and then written
+//! with `Controller::set_color` or `ControllerChain::set_color`, which batch the
+//! components of every board into a single I2C transaction.
+
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::error;
+
+use libm;
+
+use crate::channel::{
+    base::Channel,
+    curve::BrightnessCurve,
+    errors,
+    led::{ Brightness, LedChannel },
+};
+
+/// A colour as red, green and blue fractions (`0.0..1.0`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rgb {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+}
+
+impl Rgb {
+
+    /// Creates an `Rgb` colour from fractions (`0.0..1.0`).
+    pub fn new(red: f32, green: f32, blue: f32) -> Rgb {
+        Rgb{
+            red,
+            green,
+            blue,
+        }
+    }
+
+    /// Creates an `Rgb` colour from 8-bit components.
+    pub fn from_u8(red: u8, green: u8, blue: u8) -> Rgb {
+        Rgb::new(red as f32 / 255.0, green as f32 / 255.0, blue as f32 / 255.0)
+    }
+
+    /// Creates an `Rgb` colour from a `hue` in degrees and a `saturation` and `value`
+    /// as fractions (`0.0..1.0`).
+    ///
+    /// ```
+    /// # extern crate rust_pca9685;
+    /// # use rust_pca9685::rgb::Rgb;
+    /// #
+    /// assert_eq!(Rgb::new(0.0, 1.0, 0.0), Rgb::from_hsv(120.0, 1.0, 1.0));
+    /// assert_eq!(Rgb::new(0.5, 0.25, 0.25), Rgb::from_hsv(-360.0, 0.5, 0.5));
+    /// ```
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Rgb {
+        let hue = libm::fmodf(libm::fmodf(hue, 360.0) + 360.0, 360.0) / 60.0;
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - libm::fabsf(libm::fmodf(hue, 2.0) - 1.0));
+        let m = value - chroma;
+
+        let (red, green, blue) = match hue as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        Rgb::new(red + m, green + m, blue + m)
+    }
+
+    /// Parses a `#rrggbb` or `#rgb` hex string, the leading `#` is optional.
+    ///
+    /// ```
+    /// # extern crate rust_pca9685;
+    /// # use rust_pca9685::rgb::Rgb;
+    /// #
+    /// assert_eq!(Rgb::from_u8(0xff, 0x80, 0x00), Rgb::from_hex("#ff8000").unwrap());
+    /// assert_eq!(Rgb::from_u8(0xff, 0x88, 0x00), Rgb::from_hex("f80").unwrap());
+    /// assert!(Rgb::from_hex("#ff80").is_err());
+    /// ```
+    pub fn from_hex(hex: &str) -> Result<Rgb, ParseColorError> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(ParseColorError);
+        }
+
+        let value = u32::from_str_radix(digits, 16).map_err(|_| ParseColorError)?;
+        match digits.len() {
+            6 => Ok(Rgb::from(value)),
+            3 => {
+                let expand = |nibble: u32| ((nibble & 0xf) * 0x11) as u8;
+                Ok(Rgb::from_u8(expand(value >> 8), expand(value >> 4), expand(value)))
+            },
+            _ => Err(ParseColorError),
+        }
+    }
+
+}
+
+impl From<u32> for Rgb {
+
+    /// Creates an `Rgb` colour from a `0xRRGGBB` value.
+    fn from(value: u32) -> Rgb {
+        Rgb::from_u8((value >> 16) as u8, (value >> 8) as u8, value as u8)
+    }
+
+}
+
+impl FromStr for Rgb {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Rgb, ParseColorError> {
+        Rgb::from_hex(s)
+    }
+}
+
+/// A colour as red, green, blue and white fractions (`0.0..1.0`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rgbw {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32,
+    pub white: f32,
+}
+
+impl Rgbw {
+
+    /// Creates an `Rgbw` colour from fractions (`0.0..1.0`).
+    pub fn new(red: f32, green: f32, blue: f32, white: f32) -> Rgbw {
+        Rgbw{
+            red,
+            green,
+            blue,
+            white,
+        }
+    }
+
+}
+
+impl From<Rgb> for Rgbw {
+
+    /// Moves the part shared by all three components of `rgb` onto the white channel.
+    fn from(rgb: Rgb) -> Rgbw {
+        let white = rgb.red.min(rgb.green).min(rgb.blue).max(0.0);
+        Rgbw::new(rgb.red - white, rgb.green - white, rgb.blue - white, white)
+    }
+
+}
+
+/// Error returned when a hex colour string cannot be parsed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseColorError;
+
+#[cfg(feature = "std")]
+impl error::Error for ParseColorError { }
+
+impl fmt::Display for ParseColorError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid hex colour, expected #rrggbb or #rgb")
+    }
+
+}
+
+/// One channel of a colour LED, on the board at position `board` of a `ControllerChain`.
+#[derive(Clone, Copy, Debug)]
+struct Component {
+    board: usize,
+    channel: LedChannel,
+    balance: f32,
+}
+
+impl Component {
+
+    fn new((board, channel_num): (usize, u8)) -> Result<Component, errors::IndexRangeError> {
+        Ok(
+            Component{
+                board,
+                channel: LedChannel::new(channel_num)?,
+                balance: 1.0,
+            }
+        )
+    }
+
+    fn set_balance(&mut self, balance: f32) -> Result<(), errors::ValueRangeError> {
+        self.balance = Brightness::Fraction(balance).fraction()?;
+        Ok(())
+    }
+
+    fn output(&self, value: f32) -> Result<ComponentOutput, errors::ValueRangeError> {
+        let value = Brightness::Fraction(value).fraction()?;
+        Ok(
+            ComponentOutput{
+                board: self.board,
+                channel_num: self.channel.channel_num(),
+                ticks: self.channel.brightness_to_ticks(value * self.balance)?,
+            }
+        )
+    }
+
+}
+
+/// The number of counter steps per period a component channel of a colour LED is on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComponentOutput {
+    /// Position of the component's board in a `ControllerChain`.
+    pub board: usize,
+    /// Channel number of the component on its board.
+    pub channel_num: u8,
+    /// Counter steps per period (`0..4096`) the channel is on.
+    pub ticks: u16,
+}
+
+/// An LED made of several channels that are driven together to show a colour.
+pub trait ColorLed {
+    /// Colour type the LED displays.
+    type Color: Copy;
+
+    /// Calculates the output of every component for `color`. Returns an error
+    /// if a component of `color` is outside of `0.0..1.0`.
+    fn outputs(&self, color: Self::Color) -> Result<ComponentOutputs, errors::ValueRangeError>;
+}
+
+/// Outputs of the components of a colour LED, see `ColorLed::outputs`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComponentOutputs {
+    outputs: [ComponentOutput; 4],
+    len: usize,
+}
+
+impl ComponentOutputs {
+
+    fn from_slice(outputs: &[ComponentOutput]) -> ComponentOutputs {
+        let mut all = [ComponentOutput::default(); 4];
+        all[..outputs.len()].copy_from_slice(outputs);
+
+        ComponentOutputs{
+            outputs: all,
+            len: outputs.len(),
+        }
+    }
+
+    /// Returns the outputs in component order.
+    pub fn as_slice(&self) -> &[ComponentOutput] {
+        &self.outputs[..self.len]
+    }
+
+}
+
+/// An RGB LED on three channels.
+#[derive(Clone, Copy, Debug)]
+pub struct RgbLed {
+    components: [Component; 3],
+}
+
+impl RgbLed {
+
+    /// Creates an `RgbLed` whose components are on channels `red`, `green` and `blue` of one board.
+    pub fn new(red: u8, green: u8, blue: u8) -> Result<RgbLed, errors::IndexRangeError> {
+        RgbLed::spanning((0, red), (0, green), (0, blue))
+    }
+
+    /// Creates an `RgbLed` from `(board, channel)` pairs, for components on different
+    /// boards of a `ControllerChain`.
+    pub fn spanning(red: (usize, u8), green: (usize, u8), blue: (usize, u8)) -> Result<RgbLed, errors::IndexRangeError> {
+        Ok(
+            RgbLed{
+                components: [Component::new(red)?, Component::new(green)?, Component::new(blue)?],
+            }
+        )
+    }
+
+    /// Maps the brightness of every component through `curve`.
+    pub fn with_curve(mut self, curve: BrightnessCurve) -> RgbLed {
+        self.components.iter_mut().for_each(|component| component.channel.set_curve(curve));
+        self
+    }
+
+    /// Scales each component by a factor (`0.0..1.0`), to even out LEDs whose
+    /// colours differ in intensity so that full red, green and blue look white.
+    /// Returns an error if a factor is outside of `0.0..1.0`.
+    pub fn with_white_balance(mut self, red: f32, green: f32, blue: f32) -> Result<RgbLed, errors::ValueRangeError> {
+        for (component, balance) in self.components.iter_mut().zip(&[red, green, blue]) {
+            component.set_balance(*balance)?;
+        }
+        Ok(self)
+    }
+
+    /// Returns the red, green and blue channels.
+    pub fn channels(&self) -> [LedChannel; 3] {
+        [self.components[0].channel, self.components[1].channel, self.components[2].channel]
+    }
+
+}
+
+impl ColorLed for RgbLed {
+    type Color = Rgb;
+
+    fn outputs(&self, color: Rgb) -> Result<ComponentOutputs, errors::ValueRangeError> {
+        Ok(ComponentOutputs::from_slice(&[
+            self.components[0].output(color.red)?,
+            self.components[1].output(color.green)?,
+            self.components[2].output(color.blue)?,
+        ]))
+    }
+}
+
+/// An RGBW LED on four channels.
+#[derive(Clone, Copy, Debug)]
+pub struct RgbwLed {
+    components: [Component; 4],
+}
+
+impl RgbwLed {
+
+    /// Creates an `RgbwLed` whose components are on channels `red`, `green`, `blue` and `white` of one board.
+    pub fn new(red: u8, green: u8, blue: u8, white: u8) -> Result<RgbwLed, errors::IndexRangeError> {
+        RgbwLed::spanning((0, red), (0, green), (0, blue), (0, white))
+    }
+
+    /// Creates an `RgbwLed` from `(board, channel)` pairs, for components on different
+    /// boards of a `ControllerChain`.
+    pub fn spanning(red: (usize, u8), green: (usize, u8), blue: (usize, u8), white: (usize, u8)) -> Result<RgbwLed, errors::IndexRangeError> {
+        Ok(
+            RgbwLed{
+                components: [Component::new(red)?, Component::new(green)?, Component::new(blue)?, Component::new(white)?],
+            }
+        )
+    }
+
+    /// Maps the brightness of every component through `curve`.
+    pub fn with_curve(mut self, curve: BrightnessCurve) -> RgbwLed {
+        self.components.iter_mut().for_each(|component| component.channel.set_curve(curve));
+        self
+    }
+
+    /// Scales each component by a factor (`0.0..1.0`), see `RgbLed::with_white_balance`.
+    pub fn with_white_balance(mut self, red: f32, green: f32, blue: f32, white: f32) -> Result<RgbwLed, errors::ValueRangeError> {
+        for (component, balance) in self.components.iter_mut().zip(&[red, green, blue, white]) {
+            component.set_balance(*balance)?;
+        }
+        Ok(self)
+    }
+
+    /// Returns the red, green, blue and white channels.
+    pub fn channels(&self) -> [LedChannel; 4] {
+        [self.components[0].channel, self.components[1].channel, self.components[2].channel, self.components[3].channel]
+    }
+
+}
+
+impl ColorLed for RgbwLed {
+    type Color = Rgbw;
+
+    fn outputs(&self, color: Rgbw) -> Result<ComponentOutputs, errors::ValueRangeError> {
+        Ok(ComponentOutputs::from_slice(&[
+            self.components[0].output(color.red)?,
+            self.components[1].output(color.green)?,
+            self.components[2].output(color.blue)?,
+            self.components[3].output(color.white)?,
+        ]))
+    }
+}
@@ -0,0 +1,128 @@
+//! Sharing a single `Controller` between threads.
+//!
+//! A `SharedController` moves a `Controller` behind an `Arc<Mutex<_>>` and hands out
+//! `ControllerHandle`s. Handles are cheap to clone and `Send`, so separate subsystems
+//! (a pan/tilt head and a status LED, say) can each drive their own channels on the
+//! same board. Every call on a handle locks the controller for the duration of that call.
+
+use std::sync::{ Arc, Mutex, MutexGuard, PoisonError };
+
+use embedded_hal::delay::DelayNs;
+
+use crate::{
+    channel::{
+        base::Channel,
+        output::Output,
+        servo::ServoChannel,
+        state::ChannelState,
+    },
+    controller::Controller,
+    device::Device,
+    error::Error,
+};
+
+/// Owner of a `Controller` that is shared between `ControllerHandle`s.
+#[derive(Debug)]
+pub struct SharedController<T: Device, D: DelayNs> {
+    inner: Arc<Mutex<Controller<T, D>>>,
+}
+
+impl<T: Device, D: DelayNs> SharedController<T, D> {
+
+    /// Moves `controller` behind a mutex so it can be shared.
+    pub fn new(controller: Controller<T, D>) -> SharedController<T, D> {
+        SharedController{
+            inner: Arc::new(Mutex::new(controller)),
+        }
+    }
+
+    /// Returns a new handle to the shared controller.
+    pub fn handle(&self) -> ControllerHandle<T, D> {
+        ControllerHandle{
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Locks the controller for a sequence of calls that must not be interleaved
+    /// with calls from other handles.
+    pub fn lock(&self) -> MutexGuard<'_, Controller<T, D>> {
+        lock(&self.inner)
+    }
+
+    /// Returns the `Controller` once every handle has been dropped, or
+    /// `self` back if there are handles left.
+    pub fn into_inner(self) -> Result<Controller<T, D>, SharedController<T, D>> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(mutex) => Ok(mutex.into_inner().unwrap_or_else(PoisonError::into_inner)),
+            Err(inner) => Err(SharedController{ inner }),
+        }
+    }
+
+}
+
+/// A cloneable handle to a `SharedController`.
+#[derive(Debug)]
+pub struct ControllerHandle<T: Device, D: DelayNs> {
+    inner: Arc<Mutex<Controller<T, D>>>,
+}
+
+impl<T: Device, D: DelayNs> Clone for ControllerHandle<T, D> {
+
+    fn clone(&self) -> ControllerHandle<T, D> {
+        ControllerHandle{
+            inner: self.inner.clone(),
+        }
+    }
+
+}
+
+impl<T: Device, D: DelayNs> ControllerHandle<T, D> {
+
+    /// Locks the controller for a sequence of calls that must not be interleaved
+    /// with calls from other handles.
+    pub fn lock(&self) -> MutexGuard<'_, Controller<T, D>> {
+        lock(&self.inner)
+    }
+
+    /// Runs `f` with the controller locked and returns its result.
+    pub fn with<R, F: FnOnce(&mut Controller<T, D>) -> R>(&self, f: F) -> R {
+        f(&mut self.lock())
+    }
+
+    /// Sets `channel`'s registers to drive `output`, see `Controller::set_channel`.
+    pub fn set_channel<C: Channel, O: Into<Output>>(&self, channel: &mut C, output: O) -> Result<(), Error<T::Error>> {
+        self.lock().set_channel(channel, output)
+    }
+
+    /// Sets `channel` to output a pulse of `pulse_width` µs, see `Controller::set_pulse_width`.
+    pub fn set_pulse_width<C: Channel>(&self, channel: &mut C, pulse_width: u16) -> Result<(), Error<T::Error>> {
+        self.lock().set_pulse_width(channel, pulse_width)
+    }
+
+    /// Moves the servo on `channel` to `angle` degrees, see `Controller::set_servo_angle`.
+    pub fn set_servo_angle(&self, channel: &mut ServoChannel, angle: f32) -> Result<(), Error<T::Error>> {
+        self.lock().set_servo_angle(channel, angle)
+    }
+
+    /// Turns `channel` fully on.
+    pub fn set_full_on<C: Channel>(&self, channel: &mut C) -> Result<(), Error<T::Error>> {
+        self.lock().set_full_on(channel)
+    }
+
+    /// Turns `channel` fully off.
+    pub fn set_full_off<C: Channel>(&self, channel: &mut C) -> Result<(), Error<T::Error>> {
+        self.lock().set_full_off(channel)
+    }
+
+    /// Reads `channel`'s registers back from the controller and decodes them.
+    pub fn get_channel<C: Channel>(&self, channel: &C) -> Result<ChannelState, Error<T::Error>> {
+        self.lock().get_channel(channel)
+    }
+
+}
+
+/// Locks `inner`. A panic on another handle while it held the lock leaves the
+/// registers as they were after the last completed write, so poisoning is ignored.
+fn lock<T: Device, D: DelayNs>(inner: &Mutex<Controller<T, D>>) -> MutexGuard<'_, Controller<T, D>> {
+    inner.lock().unwrap_or_else(PoisonError::into_inner)
+}
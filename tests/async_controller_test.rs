@@ -0,0 +1,142 @@
+#![cfg(feature = "async")]
+
+extern crate embedded_hal;
+extern crate embedded_hal_async;
+extern crate i2cdev;
+extern crate env_logger;
+extern crate rust_pca9685;
+
+mod common;
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{ Context, Poll, Waker };
+
+use embedded_hal::i2c::{ ErrorKind, I2c as _ };
+use embedded_hal_async::{ delay, i2c };
+
+use common::{ MockBus, MockDelay };
+
+use rust_pca9685::{
+    async_controller::AsyncController,
+    channel::{
+        led::LedChannel,
+        output::Output,
+        servo::ServoChannel,
+    },
+    constants,
+    mode::{ Mode1, Mode2 },
+    Error,
+};
+
+impl i2c::I2c for MockBus {
+    async fn transaction(&mut self, address: u8, operations: &mut [i2c::Operation<'_>]) -> Result<(), ErrorKind> {
+        embedded_hal::i2c::I2c::transaction(self, address, operations)
+    }
+}
+
+impl delay::DelayNs for &mut MockDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.delays_ns.push(ns);
+    }
+}
+
+/// Polls `future` to completion. Every mock future is ready on its first poll.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn test_async_controller_init() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+    let mut delay = MockDelay::new();
+
+    block_on(async {
+        let mut ctrl = AsyncController::with_default_address(&mut bus, &mut delay).await.unwrap();
+        assert_eq!(Mode1::ALLCALL | Mode1::AI, ctrl.read_mode1().await.unwrap());
+        assert_eq!(Mode2::OUTDRV, ctrl.read_mode2().await.unwrap());
+    });
+
+    assert_eq!(vec![5_000_000; 2], delay.delays_ns);
+}
+
+#[test]
+fn test_async_controller_set_frequency() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+    let mut delay = MockDelay::new();
+
+    block_on(async {
+        let mut ctrl = AsyncController::new(&mut bus, 0x40, &mut delay).await.unwrap();
+        ctrl.set_frequency(50.0).await.unwrap();
+        assert_eq!(50.0, (ctrl.pwm_rate().await.unwrap() * 10.0).round() / 10.0);
+
+        match ctrl.set_frequency(2000.0).await {
+            Err(Error::InvalidFrequency(_)) => (),
+            other => panic!("expected invalid frequency, received {:?}", other),
+        }
+    });
+
+    assert_eq!(0x79, bus.device(0x40).reg(constants::PRE_SCALE));
+    assert_eq!(0xa1, bus.device(0x40).reg(constants::MODE_1));
+    assert_eq!(3, delay.delays_ns.len());
+}
+
+#[test]
+fn test_async_controller_channels() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+    let mut delay = MockDelay::new();
+
+    block_on(async {
+        let mut ctrl = AsyncController::with_default_address(&mut bus, &mut delay).await.unwrap();
+
+        let mut servo = ServoChannel::new(3).unwrap();
+        ctrl.set_channel(&mut servo, (0x10, 0x200)).await.unwrap();
+
+        let mut led = LedChannel::new(4).unwrap();
+        ctrl.set_channel(&mut led, Output::FullOn).await.unwrap();
+
+        assert_eq!(Output::Pwm { on: 0x10, off: 0x200 }, ctrl.get_channel(&servo).await.unwrap().output());
+
+        let states = ctrl.snapshot().await.unwrap();
+        assert!(states[4].full_on());
+        assert!(!states[5].full_on());
+
+        ctrl.set_all_channels(Output::FullOff).await.unwrap();
+    });
+
+    let writes = bus.device(0x40).channel_writes();
+    assert_eq!(vec![
+        vec![0x12, 0x10, 0x00, 0x00, 0x02],
+        vec![0x16, 0x00, 0x10, 0x00, 0x00],
+        vec![constants::ALL_LED_ON_LOW, 0x00, 0x00, 0x00, 0x10],
+    ], writes);
+}
+
+#[test]
+fn test_async_controller_missing_device() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+    let mut delay = MockDelay::new();
+
+    match block_on(AsyncController::new(&mut bus, 0x41, &mut delay)) {
+        Err(Error::Bus(ErrorKind::NoAcknowledge(_))) => (),
+        other => panic!("expected bus error, received {:?}", other.map(|_| ())),
+    }
+
+    // The blocking bus is still usable afterwards
+    bus.write(0x40, &[constants::MODE_1]).unwrap();
+}
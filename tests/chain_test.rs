@@ -0,0 +1,104 @@
+extern crate embedded_hal;
+extern crate i2cdev;
+extern crate env_logger;
+extern crate rust_pca9685;
+
+mod common;
+
+use common::MockDevice;
+
+use rust_pca9685::{
+    chain::{ ChainChannel, ControllerChain },
+    channel::{
+        output::Output,
+        servo::ServoSettings,
+    },
+    constants,
+    controller::Controller,
+    delay::StdDelay,
+    Error,
+};
+
+fn chain_of(addresses: &[u16]) -> ControllerChain<MockDevice, StdDelay> {
+    let mut chain = ControllerChain::new();
+    for address in addresses {
+        chain.add_board(*address, Controller::new(MockDevice::new()).unwrap()).unwrap();
+    }
+
+    chain
+}
+
+#[test]
+fn test_chain_channel_indexing() {
+    assert_eq!(ChainChannel::new(0, 15), ChainChannel::from(15));
+    assert_eq!(ChainChannel::new(1, 0), ChainChannel::from(16));
+    assert_eq!(ChainChannel::new(2, 3), ChainChannel::from((2, 3)));
+    assert_eq!(35, ChainChannel::new(2, 3).flat_index());
+}
+
+#[test]
+fn test_chain_add_board() {
+    let _ = env_logger::try_init();
+
+    let mut chain = chain_of(&[0x40, 0x41]);
+    assert_eq!(2, chain.len());
+    assert_eq!(32, chain.num_channels());
+    assert_eq!(Some(1), chain.position(0x41));
+    assert_eq!(0x41, chain.board(1).unwrap().address());
+
+    match chain.add_board(0x40, Controller::new(MockDevice::new()).unwrap()) {
+        Err(Error::DuplicateAddress(0x40)) => (),
+        other => panic!("expected duplicate address error, received {:?}", other),
+    }
+    assert_eq!(2, chain.len());
+}
+
+#[test]
+fn test_chain_dispatches_to_boards() {
+    let _ = env_logger::try_init();
+
+    let mut chain = chain_of(&[0x40, 0x41, 0x42]);
+
+    chain.set_channel(3, (0, 0x100)).unwrap();
+    chain.set_channel((1, 3), (0, 0x200)).unwrap();
+    chain.set_channel(35, Output::FullOn).unwrap();
+
+    assert_eq!(Output::Pwm { on: 0, off: 0x200 }, chain.get_channel(19).unwrap().output());
+
+    match chain.set_channel(48, Output::FullOff) {
+        Err(Error::IndexRange(_)) => (),
+        other => panic!("expected index range error, received {:?}", other),
+    }
+    match chain.set_channel((0, 16), Output::FullOff) {
+        Err(Error::IndexRange(_)) => (),
+        other => panic!("expected index range error, received {:?}", other),
+    }
+
+    let boards = chain.into_boards();
+    let writes: Vec<Vec<Vec<u8>>> = boards.into_iter()
+        .map(|board| board.into_inner().into_inner().channel_writes())
+        .collect();
+
+    assert_eq!(vec![vec![0x12, 0x00, 0x00, 0x00, 0x01]], writes[0]);
+    assert_eq!(vec![vec![0x12, 0x00, 0x00, 0x00, 0x02]], writes[1]);
+    assert_eq!(vec![vec![0x12, 0x00, 0x10, 0x00, 0x00]], writes[2]);
+}
+
+#[test]
+fn test_chain_common_frequency() {
+    let _ = env_logger::try_init();
+
+    let mut chain = chain_of(&[0x40, 0x41]);
+    chain.set_frequency(50.0).unwrap();
+    chain.set_servo_angle(17, ServoSettings::new(1000, 2000), 90.0).unwrap();
+
+    match chain.set_frequency(2000.0) {
+        Err(Error::InvalidFrequency(_)) => (),
+        other => panic!("expected invalid frequency error, received {:?}", other),
+    }
+
+    for board in chain.into_boards() {
+        let device = board.into_inner().into_inner();
+        assert_eq!(0x79, device.reg(constants::PRE_SCALE));
+    }
+}
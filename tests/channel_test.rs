@@ -59,7 +59,7 @@ struct TestChannel {
 }
 impl Channel for TestChannel {
     fn channel_num(&self) -> u8 {
-        return self.channel_num;
+        self.channel_num
     }
 }
 impl TestChannel {
@@ -70,7 +70,7 @@ impl TestChannel {
 
         Ok(
             TestChannel{
-                channel_num: channel_num,
+                channel_num,
             }
         )
     }
@@ -111,7 +111,7 @@ const CHANNEL_REGISTERS: [ChannelRegisterQuad; 16] = [
 fn test_calculated_addrs_for_channel() {
     let _ = env_logger::try_init();
 
-    for i in 0..16 as u8 {
+    for i in 0..16u8 {
         debug!("calculate register addrs for channel {}", i);
 
         let result = TestChannel::new(i).unwrap();
@@ -134,8 +134,7 @@ fn test_calculated_addrs_for_channel() {
 fn test_calculated_addrs_for_channel_with_regmap() {
     let _ = env_logger::try_init();
 
-    for i in 0..16 {
-        let registers = CHANNEL_REGISTERS[i];
+    for (i, registers) in CHANNEL_REGISTERS.iter().enumerate() {
         let result = TestChannel::new(i as u8).unwrap();
 
         debug!("channel {} should have addresses {}", i, registers);
@@ -0,0 +1,289 @@
+//
+// Shared test fixtures.
+//
+// `i2cdev::mock::MockI2CDevice` underflows when reading register `0x00`
+// (`MODE_1`), so controller tests use this register map instead.
+//
+
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicBool, Ordering };
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{ ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation };
+use i2cdev::core::I2CDevice;
+
+use rust_pca9685::{
+    channel::base::Channel,
+    constants,
+    error::Error,
+    reset::{ SetSlaveAddress, SoftReset },
+};
+
+/// A PCA9685-shaped register map that speaks plain I2C reads and writes.
+/// The first byte of every write selects the register, following bytes are
+/// stored with auto-increment and reads continue from the selected register.
+pub struct MockDevice {
+    pub regs: [u8; 256],
+    pub writes: Vec<Vec<u8>>,
+    pub slave_addresses: Vec<u16>,
+    pub ignore_writes: bool,
+    pub fail_writes: Arc<AtomicBool>,
+    offset: usize,
+}
+
+impl MockDevice {
+    /// Creates a `MockDevice` with the PCA9685 power-on register values.
+    pub fn new() -> MockDevice {
+        let mut device = MockDevice{
+            regs: [0x00; 256],
+            writes: Vec::new(),
+            slave_addresses: Vec::new(),
+            ignore_writes: false,
+            fail_writes: Arc::new(AtomicBool::new(false)),
+            offset: 0,
+        };
+        device.power_on();
+
+        device
+    }
+
+    /// Returns every register to its power-on value, as a software reset does.
+    /// The write log is kept.
+    pub fn power_on(&mut self) {
+        self.regs = [0x00; 256];
+        self.offset = 0;
+        self.set_regs(constants::MODE_1, &[0x11, 0x04, 0xe2, 0xe4, 0xe8, 0xe0]);
+        self.set_regs(constants::PRE_SCALE, &[0x1e]);
+    }
+
+    /// Returns the value currently stored in `register`.
+    pub fn reg(&self, register: u8) -> u8 {
+        self.regs[register as usize]
+    }
+
+    /// Returns the logged writes that stored data in a channel register.
+    pub fn channel_writes(&self) -> Vec<Vec<u8>> {
+        self.writes.iter()
+            .filter(|write| write.len() > 1 && write[0] >= constants::BASE_LED_ON_LOW)
+            .cloned()
+            .collect()
+    }
+
+    /// Stores `data` starting at `register`, bypassing the write log.
+    pub fn set_regs(&mut self, register: u8, data: &[u8]) {
+        let start = register as usize;
+        self.regs[start..start + data.len()].copy_from_slice(data);
+    }
+
+    /// Returns whether the device answers on the shared `address`, following
+    /// the `ALLCALL`/`SUBx` bits of `MODE_1` and the programmed address registers.
+    pub fn answers_on(&self, address: u8) -> bool {
+        let mode1 = self.reg(constants::MODE_1);
+        let shared = [
+            (constants::ALL_CALL, constants::ALL_CALL_ADDR),
+            (constants::SUB1, constants::SUBADDR_1),
+            (constants::SUB2, constants::SUBADDR_2),
+            (constants::SUB3, constants::SUBADDR_3),
+        ];
+
+        shared.iter().any(|&(flag, register)| mode1 & flag != 0 && self.reg(register) >> 1 == address)
+    }
+}
+
+/// The mock stands alone on its bus, so a General Call only reaches itself.
+impl SoftReset for MockDevice {
+    fn soft_reset(&mut self) -> Result<(), Error<io::Error>> {
+        self.power_on();
+        Ok(())
+    }
+}
+
+/// Slave address switches are only logged in `slave_addresses`.
+impl SetSlaveAddress for MockDevice {
+    fn set_slave_address(&mut self, slave_address: u16) -> io::Result<()> {
+        self.slave_addresses.push(slave_address);
+        Ok(())
+    }
+}
+
+/// A downstream `Channel` that claims a channel number past the controller's 16.
+pub struct OutOfRangeChannel;
+
+impl Channel for OutOfRangeChannel {
+    fn channel_num(&self) -> u8 {
+        constants::NUM_CHANNELS as u8
+    }
+}
+
+fn unsupported() -> io::Error {
+    io::Error::other("unsupported by MockDevice")
+}
+
+impl I2CDevice for MockDevice {
+    type Error = io::Error;
+
+    fn read(&mut self, data: &mut [u8]) -> io::Result<()> {
+        let end = self.offset + data.len();
+        data.copy_from_slice(&self.regs[self.offset..end]);
+        self.offset = end;
+        Ok(())
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        if self.fail_writes.load(Ordering::SeqCst) {
+            return Err(io::Error::other("write failed"));
+        }
+
+        self.writes.push(data.to_vec());
+        self.offset = data[0] as usize;
+        if self.ignore_writes {
+            return Ok(());
+        }
+
+        let end = self.offset + data.len() - 1;
+        self.regs[self.offset..end].copy_from_slice(&data[1..]);
+        if data.len() > 1 {
+            self.offset = end;
+        }
+        Ok(())
+    }
+
+    fn smbus_write_quick(&mut self, _bit: bool) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    fn smbus_read_block_data(&mut self, _register: u8) -> io::Result<Vec<u8>> {
+        Err(unsupported())
+    }
+
+    fn smbus_read_i2c_block_data(&mut self, _register: u8, _len: u8) -> io::Result<Vec<u8>> {
+        Err(unsupported())
+    }
+
+    fn smbus_write_block_data(&mut self, _register: u8, _values: &[u8]) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    fn smbus_write_i2c_block_data(&mut self, _register: u8, _values: &[u8]) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    fn smbus_process_block(&mut self, _register: u8, _values: &[u8]) -> io::Result<Vec<u8>> {
+        Err(unsupported())
+    }
+}
+
+/// An `embedded-hal` I2C bus with a `MockDevice` behind every registered address.
+/// Writes to the general call address (`0x00`) are logged in `general_calls`,
+/// and a `SWRST` puts every device back into its power-on state.
+pub struct MockBus {
+    pub devices: BTreeMap<u8, MockDevice>,
+    pub general_calls: Vec<Vec<u8>>,
+}
+
+impl MockBus {
+    /// Creates a `MockBus` with a freshly powered-on PCA9685 at each of `addresses`.
+    pub fn new(addresses: &[u8]) -> MockBus {
+        MockBus{
+            devices: addresses.iter().map(|address| (*address, MockDevice::new())).collect(),
+            general_calls: Vec::new(),
+        }
+    }
+
+    /// Returns the device at `address`.
+    pub fn device(&self, address: u8) -> &MockDevice {
+        &self.devices[&address]
+    }
+}
+
+impl ErrorType for MockBus {
+    type Error = ErrorKind;
+}
+
+impl I2c for MockBus {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation]) -> Result<(), ErrorKind> {
+        if address == 0x00 {
+            for operation in operations.iter() {
+                if let Operation::Write(data) = operation {
+                    self.general_calls.push(data.to_vec());
+                    if data[..] == [constants::SOFT_RESET] {
+                        self.devices.values_mut().for_each(MockDevice::power_on);
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(device) = self.devices.get_mut(&address) {
+            return apply(device, operations);
+        }
+
+        // Subaddresses and the All Call address are write-only and shared by every member
+        let mut members = self.devices.values_mut()
+            .filter(|device| device.answers_on(address))
+            .peekable();
+
+        if members.peek().is_none() {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+
+        for device in members {
+            if operations.iter().any(|operation| matches!(operation, Operation::Read(_))) {
+                return Err(ErrorKind::Other);
+            }
+            apply(device, operations)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs `operations` against `device`, merging adjacent writes into a single I2C write.
+fn apply(device: &mut MockDevice, operations: &mut [Operation]) -> Result<(), ErrorKind> {
+    let mut pending: Vec<u8> = Vec::new();
+    for operation in operations.iter_mut() {
+        match operation {
+            Operation::Write(data) => pending.extend_from_slice(data),
+            Operation::Read(buf) => {
+                if !pending.is_empty() {
+                    device.write(&pending).map_err(|_| ErrorKind::Other)?;
+                    pending.clear();
+                }
+                device.read(buf).map_err(|_| ErrorKind::Other)?;
+            },
+        }
+    }
+
+    if !pending.is_empty() {
+        device.write(&pending).map_err(|_| ErrorKind::Other)?;
+    }
+
+    Ok(())
+}
+
+/// A `DelayNs` that records every requested delay instead of waiting.
+#[derive(Default)]
+pub struct MockDelay {
+    pub delays_ns: Vec<u32>,
+}
+
+impl MockDelay {
+    pub fn new() -> MockDelay {
+        MockDelay::default()
+    }
+
+    /// Total time that would have been waited, in µs.
+    pub fn total_us(&self) -> u64 {
+        self.delays_ns.iter().map(|&ns| ns as u64).sum::<u64>() / 1000
+    }
+}
+
+impl DelayNs for &mut MockDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.delays_ns.push(ns);
+    }
+}
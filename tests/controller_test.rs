@@ -1,24 +1,39 @@
+extern crate embedded_hal;
 extern crate i2cdev;
-#[macro_use]
-extern crate log;
 extern crate env_logger;
 extern crate rust_pca9685;
 
+mod common;
+
+use std::error::Error as StdError;
+use std::io;
+
 use i2cdev::core::I2CDevice;
-use i2cdev::mock::MockI2CDevice;
+
+use common::{ MockDelay, MockDevice, OutOfRangeChannel };
 
 #[allow(unused_imports)]
 use rust_pca9685::{
     constants,
+    delay::StdDelay,
     controller::{
         calculate_prescale_value,
+        calibrate_oscillator_frequency,
+        checked_prescale_value,
+        prescale_to_frequency,
         Controller,
     },
     channel::{
         base::Channel,
         led::LedChannel,
-        servo::ServoChannel,
+        output::Output,
+        phase::PhaseOffset,
+        servo::{ ServoChannel, ServoSettings },
     },
+    batch::Batch,
+    mode::{ Mode1, Mode2, OutputConfig, OutputDisabled, OutputDriver, UpdateOn },
+    reset,
+    Error,
 };
 
 #[test]
@@ -26,42 +41,90 @@ fn test_calculate_prescale_values() {
     assert_eq!(0x1e, calculate_prescale_value(200f32));
 }
 
+#[test]
+fn test_prescale_to_frequency_roundtrip() {
+    for rate in &[24f32, 50.0, 60.0, 200.0, 1000.0, 1526.0] {
+        let prescale_value = calculate_prescale_value(*rate);
+        let actual = prescale_to_frequency(prescale_value);
+        assert_eq!(prescale_value, calculate_prescale_value(actual));
+    }
+}
+
+#[test]
+fn test_checked_prescale_value_range() {
+    assert_eq!(Some(0xfd), checked_prescale_value(24f32).ok());
+    assert_eq!(Some(constants::PRE_SCALE_MIN), checked_prescale_value(1526f32).ok());
+    assert!(checked_prescale_value(23f32).is_err());
+    assert!(checked_prescale_value(1800f32).is_err());
+}
+
 #[test]
 fn test_controller_init() {
     let _ = env_logger::try_init();
 
-    let mut device = MockI2CDevice::new();
+    let mut device = MockDevice::new();
 
     // Write the default values to `MODE_*` registers
     device.smbus_write_byte_data(constants::MODE_1, 0x11).unwrap();
     device.smbus_write_byte_data(constants::MODE_2, 0x04).unwrap();
 
     // Run the controller initialization step
-    Controller::new(&mut device);
+    let mut device = Controller::new(device).unwrap().into_inner();
 
     let mode1 = device.smbus_read_byte_data(constants::MODE_1).unwrap();
     let mode2 = device.smbus_read_byte_data(constants::MODE_2).unwrap();
 
-    assert_eq!(0x01, mode1);
+    assert_eq!(0x21, mode1);
     assert_eq!(0x04, mode2);
 }
 
+#[test]
+fn test_controller_read_mode_registers() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+
+    let mut ctrl = Controller::new(device).unwrap();
+    assert_eq!(Mode1::ALLCALL | Mode1::AI, ctrl.read_mode1().unwrap());
+    assert_eq!(Mode2::OUTDRV, ctrl.read_mode2().unwrap());
+}
+
+#[test]
+fn test_controller_modify_mode_registers() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+
+    let mut ctrl = Controller::new(device).unwrap();
+
+    let mode1 = ctrl.modify_mode1(|mode| mode | Mode1::SUB1 | Mode1::SUB3).unwrap();
+    assert_eq!(Mode1::ALLCALL | Mode1::AI | Mode1::SUB1 | Mode1::SUB3, mode1);
+
+    let mode2 = ctrl.modify_mode2(|mode| (mode - Mode2::OUTDRV) | Mode2::INVRT | Mode2::OUTNE1).unwrap();
+    assert_eq!(Mode2::INVRT | Mode2::OUTNE1, mode2);
+
+    let device = ctrl.into_inner();
+
+    assert_eq!(constants::ALL_CALL | constants::AI | constants::SUB1 | constants::SUB3, device.reg(constants::MODE_1));
+    assert_eq!(constants::INVRT | constants::OUTNE_1, device.reg(constants::MODE_2));
+}
+
 #[test]
 fn test_controller_set_pwm_rate() {
     let _ = env_logger::try_init();
 
     let prescale_value = calculate_prescale_value(60f32);
 
-    let mut device = MockI2CDevice::new();
+    let device = MockDevice::new();
 
-    {
-        let mut ctrl = Controller::new(&mut device);
-        ctrl.set_pwm_rate(prescale_value).unwrap();
-    }
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.set_pwm_rate(prescale_value).unwrap();
+
+    let mut device = ctrl.into_inner();
 
     let mode1 = device.smbus_read_byte_data(constants::MODE_1).unwrap();
     let prescale_reg = device.smbus_read_byte_data(constants::PRE_SCALE).unwrap();
-    assert_eq!(constants::ALL_CALL | constants::RESTART, mode1);
+    assert_eq!(constants::ALL_CALL | constants::AI | constants::RESTART, mode1);
     assert_eq!(prescale_value, prescale_reg);
 }
 
@@ -84,13 +147,13 @@ fn test_write_value_to_ledchannel() {
     assert_eq!(expected[2], 0xccu8);  // OFF_L
     assert_eq!(expected[3], 0x4u8);  // OFF_H
 
-    let mut device = MockI2CDevice::new();
+    let device = MockDevice::new();
 
-    {
-        let mut ctrl = Controller::new(&mut device);
-        let mut channel = LedChannel::new(0).unwrap();
-        ctrl.set_channel(&mut channel, on, off).unwrap();
-    }
+    let mut ctrl = Controller::new(device).unwrap();
+    let mut channel = LedChannel::new(0).unwrap();
+    ctrl.set_channel(&mut channel, (on, off)).unwrap();
+
+    let mut device = ctrl.into_inner();
 
     let channel = LedChannel::new(0).unwrap();
     let actual = channel.read_channel(&mut device).unwrap();
@@ -117,16 +180,619 @@ fn test_write_value_to_servochannel() {
     assert_eq!(expected[2], 0xccu8);  // OFF_L
     assert_eq!(expected[3], 0x4u8);  // OFF_H
 
-    let mut device = MockI2CDevice::new();
+    let device = MockDevice::new();
 
-    {
-        let mut ctrl = Controller::new(&mut device);
-        let mut channel = ServoChannel::new(0).unwrap();
-        ctrl.set_channel(&mut channel, on, off).unwrap();
-    }
+    let mut ctrl = Controller::new(device).unwrap();
+    let mut channel = ServoChannel::new(0).unwrap();
+    ctrl.set_channel(&mut channel, (on, off)).unwrap();
+
+    let mut device = ctrl.into_inner();
 
     let channel = ServoChannel::new(0).unwrap();
     let actual = channel.read_channel(&mut device).unwrap();
 
     assert_eq!(expected, actual);
-}
\ No newline at end of file
+}
+#[test]
+fn test_set_channel_uses_block_write() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+
+    let mut ctrl = Controller::new(device).unwrap();
+    let mut channel = LedChannel::new(2).unwrap();
+    ctrl.set_channel(&mut channel, (0x199, 0x4cc)).unwrap();
+
+    let device = ctrl.into_inner();
+
+    let last = device.writes.last().unwrap();
+    assert_eq!(&vec![0x0e, 0x99, 0x01, 0xcc, 0x04], last);
+}
+
+#[test]
+fn test_set_channels_bursts_contiguous_ranges() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+    let values: Vec<(u8, u16, u16)> = (0..16u8).map(|i| (i, 0, 0x100 + i as u16)).collect();
+
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.set_channels(&values).unwrap();
+
+    let mut device = ctrl.into_inner();
+
+    let burst = device.writes.last().unwrap();
+    assert_eq!(65, burst.len());
+    assert_eq!(constants::BASE_LED_ON_LOW, burst[0]);
+
+    for i in 0..16u8 {
+        let channel = LedChannel::new(i).unwrap();
+        let stored = channel.read_channel(&mut device).unwrap();
+        assert_eq!([0x00, 0x00, i, 0x01], stored);
+    }
+}
+
+#[test]
+fn test_set_channels_splits_noncontiguous_ranges() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.set_channels(&[(3, 0, 0x200), (4, 0, 0x300), (9, 0x10, 0x400)]).unwrap();
+
+    let device = ctrl.into_inner();
+
+    let bursts = device.channel_writes();
+    assert_eq!(2, bursts.len());
+    assert_eq!(9, bursts[0].len());
+    assert_eq!(5, bursts[1].len());
+    assert_eq!(LedChannel::new(9).unwrap().base_address(), bursts[1][0]);
+}
+
+#[test]
+fn test_set_channel_without_auto_increment() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.set_auto_increment(false).unwrap();
+    assert!(!ctrl.read_mode1().unwrap().contains(Mode1::AI));
+
+    ctrl.set_channels(&[(0, 0x199, 0x4cc), (1, 0x199, 0x4cc)]).unwrap();
+
+    let mut device = ctrl.into_inner();
+
+    // Every register is written individually
+    let writes = device.channel_writes();
+    assert_eq!(8, writes.len());
+    assert!(writes.iter().all(|write| write.len() == 2));
+
+    let channel = LedChannel::new(1).unwrap();
+    assert_eq!([0x99, 0x01, 0xcc, 0x04], channel.read_channel(&mut device).unwrap());
+}
+
+#[test]
+fn test_set_all_channels_uses_block_write() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.set_all_channels((0x199, 0x4cc)).unwrap();
+
+    let device = ctrl.into_inner();
+
+    let last = device.writes.last().unwrap();
+    assert_eq!(&vec![constants::ALL_LED_ON_LOW, 0x99, 0x01, 0xcc, 0x04], last);
+}
+
+#[test]
+fn test_controller_set_frequency() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.set_frequency(50f32).unwrap();
+
+    let actual = ctrl.pwm_rate().unwrap();
+    assert!((actual - 50f32).abs() < 0.5, "expected about 50Hz, read {}", actual);
+
+    let device = ctrl.into_inner();
+
+    assert_eq!(calculate_prescale_value(50f32), device.reg(constants::PRE_SCALE));
+}
+
+#[test]
+fn test_controller_set_frequency_out_of_range() {
+    let _ = env_logger::try_init();
+
+    let mut device = MockDevice::new();
+    device.set_regs(constants::PRE_SCALE, &[0x1e]);
+
+    let mut ctrl = Controller::new(device).unwrap();
+    match ctrl.set_frequency(2000f32) {
+        Err(Error::InvalidFrequency(_)) => (),
+        other => panic!("expected frequency range error, received {:?}", other),
+    }
+
+    let device = ctrl.into_inner();
+
+    // PRE_SCALE is left untouched
+    assert_eq!(0x1e, device.reg(constants::PRE_SCALE));
+}
+
+#[test]
+fn test_calibrate_oscillator_frequency() {
+    let prescale_value = calculate_prescale_value(50f32);
+
+    // An output that runs exactly as programmed yields the nominal oscillator frequency
+    let nominal = calibrate_oscillator_frequency(prescale_value, prescale_to_frequency(prescale_value));
+    assert!((nominal - constants::OSCILLATION_FREQ).abs() < 1.0);
+}
+
+#[test]
+fn test_controller_calibrate() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.set_frequency(50f32).unwrap();
+
+    // The board runs 4% fast
+    let measured = ctrl.pwm_rate().unwrap() * 1.04;
+    let oscillator_freq = ctrl.calibrate(measured).unwrap();
+
+    assert!((oscillator_freq - 26000000f32).abs() < 1.0);
+    assert_eq!(oscillator_freq, ctrl.oscillator_frequency());
+
+    // Subsequent frequency requests account for the faster oscillator
+    ctrl.set_frequency(50f32).unwrap();
+    let actual = ctrl.pwm_rate().unwrap();
+    assert!((actual - 50f32).abs() < 0.5, "expected about 50Hz, read {}", actual);
+}
+
+#[test]
+fn test_controller_enable_external_clock() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.enable_external_clock(50000000f32).unwrap();
+    assert_eq!(50000000f32, ctrl.oscillator_frequency());
+
+    ctrl.set_frequency(200f32).unwrap();
+
+    let device = ctrl.into_inner();
+
+    let mode1 = Mode1::from_bits_truncate(device.reg(constants::MODE_1));
+    assert!(mode1.contains(Mode1::EXTCLK));
+    assert!(!mode1.contains(Mode1::SLEEP));
+
+    // Twice the oscillator frequency needs roughly twice the prescale
+    assert_eq!(0x3c, device.reg(constants::PRE_SCALE));
+}
+
+#[test]
+fn test_controller_enable_external_clock_restarts_channels() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    ctrl.set_frequency(50f32).unwrap();
+    assert!(ctrl.read_mode1().unwrap().contains(Mode1::RESTART));
+
+    ctrl.enable_external_clock(50000000f32).unwrap();
+
+    // The last MODE_1 write restarts the channels on the new clock
+    let device = ctrl.into_inner();
+    let last = device.writes.iter().rev().find(|write| write[0] == constants::MODE_1).unwrap();
+    let mode1 = Mode1::from_bits_truncate(last[1]);
+    assert!(mode1.contains(Mode1::EXTCLK | Mode1::RESTART));
+    assert!(!mode1.contains(Mode1::SLEEP));
+}
+
+#[test]
+fn test_controller_enable_external_clock_out_of_range() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+    let mut ctrl = Controller::new(device).unwrap();
+
+    match ctrl.enable_external_clock(60000000f32) {
+        Err(Error::InvalidFrequency(_)) => (),
+        other => panic!("expected oscillator range error, received {:?}", other),
+    }
+    assert_eq!(constants::OSCILLATION_FREQ, ctrl.oscillator_frequency());
+}
+
+#[test]
+fn test_controller_set_frequency_scales_range_with_oscillator() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.set_pwm_rate(0x1e).unwrap();
+
+    // 24Hz needs a PRE_SCALE beyond 0xFF with a 50MHz clock
+    ctrl.enable_external_clock(50000000f32).unwrap();
+    match ctrl.set_frequency(24f32) {
+        Err(Error::InvalidFrequency(_)) => (),
+        other => panic!("expected frequency range error, received {:?}", other),
+    }
+    assert!(ctrl.set_frequency(48f32).is_ok());
+    assert!(ctrl.set_frequency(3000f32).is_ok());
+
+    // A calibrated oscillator that runs 4% fast cannot reach 24Hz either
+    ctrl.set_oscillator_frequency(26000000f32);
+    assert!(ctrl.set_frequency(24f32).is_err());
+    assert!(ctrl.set_frequency(25f32).is_ok());
+
+    let actual = ctrl.pwm_rate().unwrap();
+    assert!((actual - 25f32).abs() < 0.5, "expected about 25Hz, read {}", actual);
+}
+
+#[test]
+fn test_set_full_on_and_full_off() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+    let mut on_channel = LedChannel::new(4).unwrap();
+    let mut off_channel = LedChannel::new(5).unwrap();
+
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.set_full_on(&mut on_channel).unwrap();
+    ctrl.set_full_off(&mut off_channel).unwrap();
+
+    let mut device = ctrl.into_inner();
+
+    assert_eq!([0x00, constants::LED_FULL, 0x00, 0x00], on_channel.read_channel(&mut device).unwrap());
+    assert_eq!(Output::FullOn, on_channel.read_output(&mut device).unwrap());
+    assert_eq!(Output::FullOff, off_channel.read_output(&mut device).unwrap());
+}
+
+#[test]
+fn test_set_all_channels_full_off() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.set_all_channels(Output::FullOff).unwrap();
+
+    let device = ctrl.into_inner();
+
+    let last = device.writes.last().unwrap();
+    assert_eq!(&vec![constants::ALL_LED_ON_LOW, 0x00, 0x00, 0x00, constants::LED_FULL], last);
+}
+
+#[test]
+fn test_get_channel_state() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+    let mut ctrl = Controller::new(device).unwrap();
+    ctrl.set_frequency(50f32).unwrap();
+
+    let mut channel = ServoChannel::new(7).unwrap();
+    ctrl.set_channel(&mut channel, (0x199, 0x4cc)).unwrap();
+
+    let state = ctrl.get_channel(&channel).unwrap();
+    assert_eq!(7, state.channel_num());
+    assert_eq!(0x199, state.on());
+    assert_eq!(0x4cc, state.off());
+    assert!(!state.full_on() && !state.full_off());
+    assert_eq!(Output::Pwm { on: 0x199, off: 0x4cc }, state.output());
+
+    // Datasheet example 1: 10% delay, 20% duty cycle
+    assert!((state.duty_cycle() - 0.2).abs() < 0.001);
+    assert!((state.pulse_width() - 0.2 * 20000f32).abs() < ctrl.tick_length() * 2.0);
+
+    ctrl.set_full_on(&mut channel).unwrap();
+    let state = ctrl.get_channel(&channel).unwrap();
+    assert!(state.full_on());
+    assert_eq!(1f32, state.duty_cycle());
+}
+
+#[test]
+fn test_snapshot_and_restore() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+    let mut ctrl = Controller::new(device).unwrap();
+
+    let values: Vec<(u8, u16, u16)> = (0..16u8).map(|i| (i, 0x10 * i as u16, 0x800)).collect();
+    ctrl.set_channels(&values).unwrap();
+    ctrl.set_full_off(&mut LedChannel::new(15).unwrap()).unwrap();
+
+    let snapshot = ctrl.snapshot().unwrap();
+    for (i, state) in snapshot.iter().take(15).enumerate() {
+        assert_eq!(i as u8, state.channel_num());
+        assert_eq!(0x10 * i as u16, state.on());
+        assert_eq!(0x800, state.off());
+    }
+    assert!(snapshot[15].full_off());
+
+    // Clobber every channel, then put the board back the way it was
+    ctrl.set_channels(&(0..16u8).map(|i| (i, 0, 0)).collect::<Vec<_>>()).unwrap();
+    ctrl.restore(&snapshot).unwrap();
+
+    assert_eq!(snapshot, ctrl.snapshot().unwrap());
+}
+
+#[test]
+fn test_controller_not_initialized() {
+    let _ = env_logger::try_init();
+
+    // A controller that never leaves its power-on `SLEEP` state
+    let mut device = MockDevice::new();
+    device.ignore_writes = true;
+
+    match Controller::new(device) {
+        Err(Error::NotInitialized) => (),
+        other => panic!("expected not initialized error, received {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_set_channels_index_out_of_range() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+
+    let mut ctrl = Controller::new(device).unwrap();
+    match ctrl.set_channels(&[(15, 0, 0x100), (16, 0, 0x100)]) {
+        Err(Error::IndexRange(_)) => (),
+        other => panic!("expected index range error, received {:?}", other),
+    }
+
+    let device = ctrl.into_inner();
+
+    assert!(device.channel_writes().is_empty());
+}
+
+fn set_up_channel(device: MockDevice, channel_num: u8) -> Result<(), Error<io::Error>> {
+    let mut ctrl = Controller::new(device)?;
+    let mut channel = LedChannel::new(channel_num)?;
+    ctrl.set_channel(&mut channel, (0, 0x800))
+}
+
+#[test]
+fn test_error_conversion_and_source() {
+    let _ = env_logger::try_init();
+
+    assert!(set_up_channel(MockDevice::new(), 3).is_ok());
+
+    let err = set_up_channel(MockDevice::new(), 16).unwrap_err();
+    match err {
+        Error::IndexRange(_) => (),
+        ref other => panic!("expected index range error, received {:?}", other),
+    }
+    assert!(err.source().is_some());
+    assert_eq!("channel index out of range (0..15)", err.to_string());
+
+    let err: Error<io::Error> = Error::Bus(io::Error::other("nack"));
+    assert_eq!("i2c bus error: nack", err.to_string());
+    assert_eq!("nack", err.source().unwrap().to_string());
+}
+
+#[test]
+fn test_take_channels() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    ctrl.set_frequency(50.0).unwrap();
+
+    let mut servo = ctrl.take_servo(3, ServoSettings::new(1000, 2000)).unwrap();
+    let mut led = ctrl.take_led(5).unwrap();
+    assert_eq!(0b0000_0000_0010_1000, ctrl.claimed_channels());
+
+    // Claimed channels drive the controller like plain ones
+    ctrl.set_servo_angle(&mut servo, 90.0).unwrap();
+    ctrl.set_full_on(&mut led).unwrap();
+    assert_eq!(2000, servo.maximum_value());
+    assert!(ctrl.get_channel(&led).unwrap().full_on());
+
+    match ctrl.take_led(3) {
+        Err(Error::ChannelInUse(3)) => (),
+        other => panic!("expected channel in use error, received {:?}", other),
+    }
+    assert_eq!("channel 3 is already claimed", Error::<io::Error>::ChannelInUse(3).to_string());
+
+    match ctrl.take_servo(16, ServoSettings::defaults()) {
+        Err(Error::IndexRange(_)) => (),
+        other => panic!("expected index range error, received {:?}", other),
+    }
+
+    // Dropping a claimed channel releases it
+    drop(servo);
+    assert_eq!(0b0000_0000_0010_0000, ctrl.claimed_channels());
+    assert!(ctrl.take_led(3).is_ok());
+    assert_eq!(0b0000_0000_0010_0000, ctrl.claimed_channels());
+}
+
+#[test]
+fn test_soft_reset_switches_slave_address() {
+    let _ = env_logger::try_init();
+
+    let mut device = MockDevice::new();
+    reset::soft_reset(&mut device, Some(0x41)).unwrap();
+
+    // The reset goes to the General Call address, then the device returns to its own
+    assert_eq!(vec![constants::I2C_MASTER_ADDRESS, 0x41], device.slave_addresses);
+    assert_eq!(Some(&vec![constants::SOFT_RESET]), device.writes.last());
+}
+
+#[test]
+fn test_reset_and_reinit() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    ctrl.set_frequency(50.0).unwrap();
+    ctrl.set_auto_increment(false).unwrap();
+    let rate = ctrl.pwm_rate().unwrap();
+
+    let mut led = LedChannel::new(2).unwrap();
+    ctrl.set_full_on(&mut led).unwrap();
+
+    ctrl.reset_and_reinit().unwrap();
+
+    // The configured frequency and auto-increment setting survive the reset
+    assert_eq!(rate, ctrl.pwm_rate().unwrap());
+    assert!(!ctrl.auto_increment());
+    assert!(!ctrl.read_mode1().unwrap().intersects(Mode1::SLEEP | Mode1::AI));
+    assert_eq!(Mode2::OUTDRV, ctrl.read_mode2().unwrap());
+
+    // Channel outputs are back at their power-on value
+    assert!(!ctrl.get_channel(&led).unwrap().full_on());
+
+    let device = ctrl.into_inner();
+    assert_eq!(0x79, device.reg(constants::PRE_SCALE));
+}
+
+#[test]
+fn test_reset_and_reinit_clears_external_clock() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    ctrl.enable_external_clock(50000000f32).unwrap();
+    ctrl.set_frequency(200f32).unwrap();
+
+    ctrl.reset_and_reinit().unwrap();
+
+    // The controller runs from the internal oscillator again
+    assert!(!ctrl.read_mode1().unwrap().contains(Mode1::EXTCLK));
+    assert_eq!(constants::OSCILLATION_FREQ, ctrl.oscillator_frequency());
+
+    // The PWM rate is recalculated for the internal oscillator
+    let actual = ctrl.pwm_rate().unwrap();
+    assert!((actual - 200f32).abs() < 5.0, "expected about 200Hz, read {}", actual);
+}
+
+#[test]
+fn test_sleep_and_wake() {
+    let _ = env_logger::try_init();
+
+    let mut delay = MockDelay::new();
+
+    let device = {
+        let mut ctrl = Controller::new_with_delay(MockDevice::new(), &mut delay).unwrap();
+        ctrl.sleep().unwrap();
+        assert!(ctrl.is_sleeping().unwrap());
+        assert_eq!(Mode1::ALLCALL | Mode1::AI | Mode1::SLEEP, ctrl.read_mode1().unwrap());
+
+        // The mock stores `RESTART` as written, standing in for the controller raising
+        // it when PWM channels were running before `SLEEP`
+        ctrl.write_mode1(Mode1::RESTART | Mode1::ALLCALL | Mode1::AI | Mode1::SLEEP).unwrap();
+
+        ctrl.wake().unwrap();
+        assert!(!ctrl.is_sleeping().unwrap());
+        ctrl.into_inner()
+    };
+
+    let mode1_writes: Vec<u8> = device.writes.iter()
+        .filter(|write| write.len() == 2 && write[0] == constants::MODE_1)
+        .map(|write| write[1])
+        .collect();
+    assert_eq!(&[0x21, 0xa1], &mode1_writes[mode1_writes.len() - 2..]);
+    assert_eq!(Some(&500_000), delay.delays_ns.last());
+}
+
+#[test]
+fn test_output_config() {
+    let _ = env_logger::try_init();
+
+    let config = OutputConfig::new().open_drain().when_disabled(OutputDisabled::High);
+    let mut ctrl = Controller::new_with_output_config(MockDevice::new(), StdDelay, config).unwrap();
+    assert_eq!(Mode2::OUTNE0, ctrl.read_mode2().unwrap());
+    assert_eq!(OutputDriver::OpenDrain, ctrl.output_config().output_driver());
+
+    // Reconfiguring at runtime keeps the output change mode
+    ctrl.modify_mode2(|mode| mode | Mode2::OCH).unwrap();
+    ctrl.set_output_config(OutputConfig::new().inverted(true)).unwrap();
+    assert_eq!(Mode2::OCH | Mode2::INVRT | Mode2::OUTDRV, ctrl.read_mode2().unwrap());
+
+    let config = OutputConfig::from_mode2(ctrl.read_mode2().unwrap());
+    assert_eq!(ctrl.output_config(), config);
+    assert!(config.is_inverted());
+    assert_eq!(OutputDisabled::Low, config.disabled_state());
+
+    // A reset brings the configured outputs back
+    ctrl.reset_and_reinit().unwrap();
+    assert_eq!(Mode2::INVRT | Mode2::OUTDRV, ctrl.read_mode2().unwrap());
+}
+
+#[test]
+fn test_update_mode_and_batch() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    assert_eq!(UpdateOn::Stop, ctrl.update_mode().unwrap());
+    ctrl.set_update_mode(UpdateOn::Ack).unwrap();
+    assert_eq!(Mode2::OCH | Mode2::OUTDRV, ctrl.read_mode2().unwrap());
+    ctrl.set_update_mode(UpdateOn::Stop).unwrap();
+    assert_eq!(UpdateOn::Stop, ctrl.update_mode().unwrap());
+
+    let mut middle = LedChannel::new(2).unwrap();
+    ctrl.set_channel(&mut middle, (0x10, 0x20)).unwrap();
+
+    let mut batch = Batch::new();
+    batch.set_channel(&LedChannel::new(1).unwrap(), (0x199, 0x4cc)).unwrap()
+        .set_full_on(&LedChannel::new(3).unwrap()).unwrap();
+    assert_eq!(2, batch.len());
+
+    // Channels past the controller's 16 are rejected
+    assert!(batch.set_full_off(&OutOfRangeChannel).is_err());
+    assert_eq!(2, batch.len());
+    ctrl.write_batch(&batch).unwrap();
+
+    // One write spans channels 1 to 3 and carries channel 2 over unchanged
+    let device = ctrl.into_inner();
+    let writes = device.channel_writes();
+    assert_eq!(vec![
+        constants::BASE_LED_ON_LOW + 4,
+        0x99, 0x01, 0xcc, 0x04,
+        0x10, 0x00, 0x20, 0x00,
+        0x00, 0x10, 0x00, 0x00,
+    ], writes[writes.len() - 1]);
+}
+
+#[test]
+fn test_phase_offset() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    ctrl.set_frequency(50.0).unwrap();
+    ctrl.set_phase_offset(PhaseOffset::Even);
+    assert_eq!(PhaseOffset::Even, ctrl.phase_offset());
+
+    // Channel 15 starts late in the period, so its pulse wraps around
+    let mut servo = ServoChannel::new(15).unwrap();
+    ctrl.set_servo_angle(&mut servo, 0.0).unwrap();
+    let state = ctrl.get_channel(&servo).unwrap();
+    match state.output() {
+        Output::Pwm { on, off } => {
+            assert_eq!(3840, on);
+            assert_eq!((3840 + state.on_ticks()) % 4096, off);
+        },
+        other => panic!("expected pwm output, received {:?}", other),
+    }
+    assert!((state.pulse_width() - 1500.0).abs() <= ctrl.tick_length());
+
+    let mut led = LedChannel::new(4).unwrap();
+    ctrl.set_phase_offset(PhaseOffset::PerChannel(100));
+    ctrl.set_duty_cycle(&mut led, 0.25).unwrap();
+    assert_eq!(Output::Pwm { on: 400, off: 1424 }, ctrl.get_channel(&led).unwrap().output());
+
+    ctrl.set_duty_cycle(&mut led, 1.0).unwrap();
+    assert!(ctrl.get_channel(&led).unwrap().full_on());
+    ctrl.set_duty_cycle(&mut led, 0.0).unwrap();
+    assert!(ctrl.get_channel(&led).unwrap().full_off());
+
+    match ctrl.set_duty_cycle(&mut led, 1.5) {
+        Err(Error::ValueRange(_)) => (),
+        other => panic!("expected value range error, received {:?}", other),
+    }
+}
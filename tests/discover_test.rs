@@ -0,0 +1,73 @@
+extern crate embedded_hal;
+extern crate i2cdev;
+extern crate env_logger;
+extern crate rust_pca9685;
+
+mod common;
+
+use common::{ MockBus, MockDevice };
+
+use rust_pca9685::{
+    constants,
+    controller::Controller,
+    device::HalDevice,
+    discover::scan,
+};
+
+#[test]
+fn test_scan_finds_boards() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40, 0x41]);
+
+    // Set up the second board, so only the first one is still at its power-on values
+    {
+        let mut ctrl = Controller::new(HalDevice::new(&mut bus, 0x41)).unwrap();
+        ctrl.set_frequency(50.0).unwrap();
+    }
+
+    let candidates = scan(&mut bus);
+    let addresses: Vec<u16> = candidates.iter().map(|candidate| candidate.address()).collect();
+    assert_eq!(vec![0x40, 0x41], addresses);
+
+    assert!(candidates[0].at_reset_defaults());
+    assert!(!candidates[1].at_reset_defaults());
+    assert_eq!(0x79, candidates[1].prescale());
+    assert_eq!(vec![constants::ALL_CALL_ADDRESS], candidates[1].shared_addresses());
+}
+
+#[test]
+fn test_scan_skips_other_devices() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+
+    // Something that is not a PCA9685, with reserved bits set
+    let mut sensor = MockDevice::new();
+    sensor.set_regs(constants::MODE_1, &[0xff; 6]);
+    bus.devices.insert(0x48, sensor);
+
+    let candidates = scan(&mut bus);
+    assert_eq!(1, candidates.len());
+    assert_eq!(0x40, candidates[0].address());
+}
+
+#[test]
+fn test_scan_filters_shared_addresses() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40, 0x41]);
+
+    {
+        let mut ctrl = Controller::new(HalDevice::new(&mut bus, 0x40)).unwrap();
+        ctrl.set_subaddress(1, 0x41).unwrap();
+        ctrl.enable_subaddress(1).unwrap();
+    }
+
+    // A device answering on the default All Call address is never a board of its own
+    bus.devices.insert(constants::ALL_CALL_ADDRESS as u8, MockDevice::new());
+
+    let candidates = scan(&mut bus);
+    let addresses: Vec<u16> = candidates.iter().map(|candidate| candidate.address()).collect();
+    assert_eq!(vec![0x40], addresses);
+}
@@ -0,0 +1,174 @@
+extern crate embedded_hal;
+extern crate i2cdev;
+extern crate env_logger;
+extern crate rust_pca9685;
+
+mod common;
+
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use common::{ MockDevice, OutOfRangeChannel };
+
+use rust_pca9685::{
+    channel::{
+        curve::BrightnessCurve,
+        led::LedChannel,
+        output::Output,
+    },
+    controller::Controller,
+    fade::{ BackgroundFader, Easing, Fader },
+    shared::SharedController,
+};
+
+#[test]
+fn test_easing_curves() {
+    for easing in &[Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut, Easing::CubicIn, Easing::CubicOut, Easing::CubicInOut] {
+        assert_eq!(0.0, easing.apply(0.0), "{:?}", easing);
+        assert_eq!(1.0, easing.apply(1.0), "{:?}", easing);
+    }
+
+    assert_eq!(0.75, Easing::EaseOut.apply(0.5));
+    assert_eq!(0.125, Easing::CubicIn.apply(0.5));
+    assert_eq!(0.125, Easing::EaseInOut.apply(0.25));
+}
+
+#[test]
+fn test_fader_tick() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    let mut red = LedChannel::new(0).unwrap();
+    let green = LedChannel::new_with_curve(1, BrightnessCurve::Gamma(2.0)).unwrap();
+    ctrl.set_brightness(&mut red, 1.0).unwrap();
+
+    let mut fader = Fader::new();
+    fader.fade(red, 0.0, Duration::from_millis(1000), Easing::Linear).unwrap();
+    fader.fade(green, 1.0, Duration::from_millis(500), Easing::EaseIn).unwrap();
+    assert!(fader.fade(green, 2.0, Duration::from_millis(500), Easing::Linear).is_err());
+
+    // Fades start at the first tick, from the brightness read back then
+    let start = Duration::from_secs(10);
+    assert!(fader.tick(&mut ctrl, start).unwrap());
+    assert_eq!(Output::FullOn, ctrl.get_channel(&red).unwrap().output());
+    assert_eq!(Output::FullOff, ctrl.get_channel(&green).unwrap().output());
+
+    assert!(fader.tick(&mut ctrl, start + Duration::from_millis(250)).unwrap());
+    assert_eq!(0.75, ctrl.brightness(&red).unwrap());
+    assert_eq!(Output::Pwm { on: 0, off: 256 }, ctrl.get_channel(&green).unwrap().output());
+    assert!((ctrl.brightness(&green).unwrap() - 0.25).abs() < 0.001);
+
+    // Green is done, red keeps going
+    assert!(fader.tick(&mut ctrl, start + Duration::from_millis(600)).unwrap());
+    assert!(!fader.is_fading(&green));
+    assert_eq!(Output::FullOn, ctrl.get_channel(&green).unwrap().output());
+
+    assert!(!fader.tick(&mut ctrl, start + Duration::from_millis(1000)).unwrap());
+    assert_eq!(Output::FullOff, ctrl.get_channel(&red).unwrap().output());
+}
+
+#[test]
+fn test_fader_cancel() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    let led = LedChannel::new(7).unwrap();
+
+    let mut fader = Fader::new();
+    fader.fade(led, 1.0, Duration::from_secs(1), Easing::Linear).unwrap();
+    fader.tick(&mut ctrl, Duration::from_secs(0)).unwrap();
+    fader.tick(&mut ctrl, Duration::from_millis(500)).unwrap();
+
+    assert!(fader.cancel(&led));
+    assert!(!fader.cancel(&led));
+
+    // Channels past the controller's 16 are never fading
+    assert!(!fader.is_fading(&OutOfRangeChannel));
+    assert!(!fader.cancel(&OutOfRangeChannel));
+    assert!(!fader.tick(&mut ctrl, Duration::from_secs(1)).unwrap());
+    assert_eq!(0.5, ctrl.brightness(&led).unwrap());
+}
+
+#[test]
+fn test_fader_retries_failed_tick() {
+    let _ = env_logger::try_init();
+
+    let device = MockDevice::new();
+    let fail_writes = device.fail_writes.clone();
+    let mut ctrl = Controller::new(device).unwrap();
+    let led = LedChannel::new(3).unwrap();
+
+    let mut fader = Fader::new();
+    fader.fade(led, 1.0, Duration::from_secs(1), Easing::Linear).unwrap();
+    fader.tick(&mut ctrl, Duration::from_secs(0)).unwrap();
+
+    // The last step fails to write, so the fade stays around for the next tick
+    fail_writes.store(true, Ordering::SeqCst);
+    assert!(fader.tick(&mut ctrl, Duration::from_secs(1)).is_err());
+    assert!(fader.is_fading(&led));
+
+    fail_writes.store(false, Ordering::SeqCst);
+    assert!(!fader.tick(&mut ctrl, Duration::from_secs(2)).unwrap());
+    assert_eq!(Output::FullOn, ctrl.get_channel(&led).unwrap().output());
+}
+
+#[test]
+fn test_background_fader() {
+    let _ = env_logger::try_init();
+
+    let shared = SharedController::new(Controller::new(MockDevice::new()).unwrap());
+    let fader = BackgroundFader::spawn(shared.handle(), Duration::from_millis(1));
+
+    let done = LedChannel::new(2).unwrap();
+    let cancelled = LedChannel::new(3).unwrap();
+    fader.fade(done, 1.0, Duration::from_millis(20), Easing::EaseInOut).unwrap();
+    fader.fade(cancelled, 1.0, Duration::from_secs(60), Easing::Linear).unwrap();
+
+    for _ in 0..1000 {
+        if !fader.lock().is_fading(&done) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert!(fader.cancel(&cancelled));
+    assert!(!fader.is_active());
+    fader.stop();
+
+    let mut ctrl = shared.lock();
+    assert_eq!(Output::FullOn, ctrl.get_channel(&done).unwrap().output());
+    assert!(ctrl.brightness(&cancelled).unwrap() < 0.1);
+}
+
+#[test]
+fn test_background_fader_with_controller_locked() {
+    let _ = env_logger::try_init();
+
+    let shared = SharedController::new(Controller::new(MockDevice::new()).unwrap());
+    let fader = BackgroundFader::spawn(shared.handle(), Duration::from_millis(1));
+
+    let first = LedChannel::new(4).unwrap();
+    let second = LedChannel::new(5).unwrap();
+    fader.fade(first, 1.0, Duration::from_millis(10), Easing::Linear).unwrap();
+
+    // The thread blocks on the controller while the fader stays usable
+    {
+        let _ctrl = shared.lock();
+        thread::sleep(Duration::from_millis(5));
+        fader.fade(second, 1.0, Duration::from_millis(10), Easing::Linear).unwrap();
+        assert!(fader.lock().is_fading(&second));
+    }
+
+    for _ in 0..1000 {
+        if !fader.is_active() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert!(!fader.is_active());
+    fader.stop();
+
+    let mut ctrl = shared.lock();
+    assert_eq!(Output::FullOn, ctrl.get_channel(&first).unwrap().output());
+    assert_eq!(Output::FullOn, ctrl.get_channel(&second).unwrap().output());
+}
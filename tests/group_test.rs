@@ -0,0 +1,114 @@
+extern crate embedded_hal;
+extern crate i2cdev;
+extern crate env_logger;
+extern crate rust_pca9685;
+
+mod common;
+
+use embedded_hal::i2c::ErrorKind;
+
+use common::MockBus;
+
+use rust_pca9685::{
+    channel::{
+        led::LedChannel,
+        output::Output,
+        servo::ServoChannel,
+    },
+    constants,
+    controller::Controller,
+    device::HalDevice,
+    group::Group,
+    mode::Mode1,
+    Error,
+};
+
+#[test]
+fn test_subaddress_programming() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+
+    {
+        let mut ctrl = Controller::new(HalDevice::new(&mut bus, 0x40)).unwrap();
+        assert_eq!(constants::SUBADDR_1_ADDRESS, ctrl.subaddress(1).unwrap());
+        assert_eq!(constants::SUBADDR_3_ADDRESS, ctrl.subaddress(3).unwrap());
+        assert_eq!(constants::ALL_CALL_ADDRESS, ctrl.allcall_address().unwrap());
+
+        ctrl.set_subaddress(2, 0x60).unwrap();
+        ctrl.enable_subaddress(2).unwrap();
+        assert_eq!(0x60, ctrl.subaddress(2).unwrap());
+        assert_eq!(Mode1::ALLCALL | Mode1::AI | Mode1::SUB2, ctrl.read_mode1().unwrap());
+
+        ctrl.set_allcall_address(0x68).unwrap();
+        ctrl.disable_allcall().unwrap();
+        ctrl.disable_subaddress(2).unwrap();
+        assert_eq!(Mode1::AI, ctrl.read_mode1().unwrap());
+
+        match ctrl.set_subaddress(4, 0x60) {
+            Err(Error::ValueRange(_)) => (),
+            other => panic!("expected value range error, received {:?}", other),
+        }
+        match ctrl.set_subaddress(1, 0x80) {
+            Err(Error::ValueRange(_)) => (),
+            other => panic!("expected value range error, received {:?}", other),
+        }
+    }
+
+    assert_eq!(0xc0, bus.device(0x40).reg(constants::SUBADDR_2));
+    assert_eq!(0xd0, bus.device(0x40).reg(constants::ALL_CALL_ADDR));
+}
+
+#[test]
+fn test_group_writes_reach_every_member() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40, 0x41, 0x42]);
+
+    for address in &[0x40, 0x41, 0x42] {
+        let mut ctrl = Controller::new(HalDevice::new(&mut bus, *address)).unwrap();
+        if *address != 0x40 {
+            ctrl.set_subaddress(3, 0x60).unwrap();
+            ctrl.enable_subaddress(3).unwrap();
+        }
+    }
+
+    {
+        let mut group = Group::all_call(&mut bus);
+        let mut channel = ServoChannel::new(2).unwrap();
+        group.set_channel(&mut channel, (0, 0x133)).unwrap();
+    }
+
+    {
+        let mut group = Group::at(&mut bus, 0x60);
+        group.set_channels(&[(4, 0, 0x100), (5, 0, 0x200)]).unwrap();
+        group.set_channel(&mut LedChannel::new(9).unwrap(), Output::FullOn).unwrap();
+    }
+
+    for address in &[0x40, 0x41, 0x42] {
+        let writes = bus.device(*address).channel_writes();
+        assert_eq!(vec![0x0e, 0x00, 0x00, 0x33, 0x01], writes[0], "board {:#04x}", address);
+
+        if *address == 0x40 {
+            assert_eq!(1, writes.len());
+        } else {
+            assert_eq!(vec![
+                vec![0x16, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02],
+                vec![0x2a, 0x00, 0x10, 0x00, 0x00],
+            ], writes[1..].to_vec(), "board {:#04x}", address);
+        }
+    }
+}
+
+#[test]
+fn test_group_without_members() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+
+    let mut group = Group::at(&mut bus, 0x60);
+    match group.set_all_channels(Output::FullOff) {
+        Err(Error::Bus(ErrorKind::NoAcknowledge(_))) => (),
+        other => panic!("expected bus error, received {:?}", other),
+    }
+}
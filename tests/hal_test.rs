@@ -0,0 +1,151 @@
+extern crate embedded_hal;
+extern crate i2cdev;
+extern crate env_logger;
+extern crate rust_pca9685;
+
+mod common;
+
+use common::{ MockBus, MockDelay };
+
+#[allow(unused_imports)]
+use rust_pca9685::{
+    constants,
+    controller::{ prescale_to_frequency, Controller },
+    channel::{
+        base::Channel,
+        led::LedChannel,
+        output::Output,
+        servo::ServoChannel,
+    },
+    device::HalDevice,
+    mode::Mode1,
+    reset,
+    Error,
+};
+
+#[test]
+fn test_hal_controller_init() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+
+    {
+        let mut ctrl = Controller::new(HalDevice::with_default_address(&mut bus)).unwrap();
+        assert_eq!(Mode1::ALLCALL | Mode1::AI, ctrl.read_mode1().unwrap());
+    }
+
+    assert_eq!(0x21, bus.device(0x40).reg(constants::MODE_1));
+    assert_eq!(0x04, bus.device(0x40).reg(constants::MODE_2));
+}
+
+#[test]
+fn test_hal_controller_injected_delay() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+    let mut delay = MockDelay::new();
+
+    {
+        let mut ctrl = Controller::new_with_delay(HalDevice::with_default_address(&mut bus), &mut delay).unwrap();
+        ctrl.set_frequency(50.0).unwrap();
+    }
+
+    // Set up waits twice on the oscillator, changing PRE_SCALE once more
+    assert_eq!(vec![5_000_000; 3], delay.delays_ns);
+    assert_eq!(15_000, delay.total_us());
+}
+
+#[test]
+fn test_hal_controller_set_channel() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x41]);
+
+    {
+        let mut ctrl = Controller::new(HalDevice::new(&mut bus, 0x41)).unwrap();
+        let mut channel = ServoChannel::new(5).unwrap();
+        ctrl.set_channel(&mut channel, (0x199, 0x4cc)).unwrap();
+
+        let state = ctrl.get_channel(&channel).unwrap();
+        assert_eq!(Output::Pwm { on: 0x199, off: 0x4cc }, state.output());
+    }
+
+    // The whole channel went out in a single I2C write
+    let writes = bus.device(0x41).channel_writes();
+    assert_eq!(vec![vec![0x1a, 0x99, 0x01, 0xcc, 0x04]], writes);
+}
+
+#[test]
+fn test_hal_channel_read_write() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+    let mut device = HalDevice::with_default_address(&mut bus);
+    let channel = LedChannel::new(9).unwrap();
+
+    channel.write_channel(&mut device, [0xca, 0xfe, 0xba, 0xbe]).unwrap();
+    assert_eq!([0xca, 0xfe, 0xba, 0xbe], channel.read_channel(&mut device).unwrap());
+
+    channel.write_channel_block(&mut device, [0xde, 0xad, 0xbe, 0xef]).unwrap();
+    assert_eq!([0xde, 0xad, 0xbe, 0xef], channel.read_channel_block(&mut device).unwrap());
+}
+
+#[test]
+fn test_hal_missing_device() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+
+    match Controller::new(HalDevice::new(&mut bus, 0x42)) {
+        Err(Error::Bus(_)) => (),
+        other => panic!("expected bus error, received {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_hal_soft_reset() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40]);
+    reset::soft_reset_i2c(&mut bus).unwrap();
+
+    {
+        let mut device = HalDevice::with_default_address(&mut bus);
+        device.soft_reset().unwrap();
+    }
+
+    assert_eq!(vec![vec![constants::SOFT_RESET], vec![constants::SOFT_RESET]], bus.general_calls);
+}
+
+#[test]
+fn test_hal_reset_and_reinit() {
+    let _ = env_logger::try_init();
+
+    let mut bus = MockBus::new(&[0x40, 0x41]);
+
+    {
+        let mut ctrl = Controller::new(HalDevice::new(&mut bus, 0x41)).unwrap();
+        ctrl.set_frequency(50.0).unwrap();
+        ctrl.reset_and_reinit().unwrap();
+        assert_eq!(prescale_to_frequency(0x79), ctrl.pwm_rate().unwrap());
+    }
+
+    // The General Call resets every board, only the reinitialized one is set up again
+    assert_eq!(vec![vec![constants::SOFT_RESET]], bus.general_calls);
+    assert_eq!(0x79, bus.device(0x41).reg(constants::PRE_SCALE));
+    assert_eq!(0x11, bus.device(0x40).reg(constants::MODE_1));
+}
+
+#[test]
+fn test_hal_controller_into_inner() {
+    let _ = env_logger::try_init();
+
+    let ctrl = Controller::new(HalDevice::new(MockBus::new(&[0x40, 0x41]), 0x41)).unwrap();
+
+    let device = ctrl.into_inner();
+    assert_eq!(0x41, device.address());
+
+    let bus = device.release();
+    assert_eq!(0x21, bus.device(0x41).reg(constants::MODE_1));
+    assert_eq!(0x11, bus.device(0x40).reg(constants::MODE_1));
+}
@@ -1,12 +1,15 @@
+extern crate embedded_hal;
 extern crate i2cdev;
-#[macro_use]
 extern crate log;
 extern crate env_logger;
 extern crate rust_pca9685;
 
-use i2cdev::core::I2CDevice;
+mod common;
+
 use i2cdev::mock::MockI2CDevice;
 
+use common::MockDevice;
+
 #[allow(unused_imports)]
 use rust_pca9685::{
     constants,
@@ -16,17 +19,16 @@ use rust_pca9685::{
     },
     channel::{
         base::Channel,
-        led::LedChannel,
+        curve::BrightnessCurve,
+        led::{ Brightness, LedChannel },
+        output::Output,
     },
 };
 
 #[test]
 fn test_ledchan_new_over_max() {
     let result = LedChannel::new(16);
-    match result {
-        Ok(_) => panic!("expected error, received ok"),
-        Err(_) => return,
-    }
+    assert!(result.is_err(), "expected error, received ok");
 }
 
 #[test]
@@ -62,3 +64,122 @@ fn test_ledchan_write_channel_bytes() {
 
     assert_eq!(channel_values, stored);
 }
+
+#[test]
+fn test_ledchan_block_write_channel_bytes() {
+    let _ = env_logger::try_init();
+
+    let mut device = MockI2CDevice::new();
+    let channel = LedChannel::new(3).unwrap();
+    let channel_values: [u8; 4] = [0xde, 0xad, 0xbe, 0xef];
+
+    // Write `channel_values` to the mock device in a single block write
+    channel.write_channel_block(&mut device, channel_values).unwrap();
+
+    // Retrieve the written bytes from the channel's registers
+    let stored = channel.read_channel_block(&mut device).unwrap();
+
+    assert_eq!(channel_values, stored);
+}
+
+#[test]
+fn test_ledchan_read_output() {
+    let _ = env_logger::try_init();
+
+    let mut device = MockI2CDevice::new();
+    let channel = LedChannel::new(2).unwrap();
+
+    device.regmap.write_regs(channel.base_address() as usize, &[0x99, 0x01, 0xcc, 0x04]);
+    assert_eq!(Output::Pwm { on: 0x199, off: 0x4cc }, channel.read_output(&mut device).unwrap());
+
+    // Full off takes precedence over full on
+    device.regmap.write_regs(channel.base_address() as usize, &[0x00, 0x10, 0x00, 0x10]);
+    assert_eq!(Output::FullOff, channel.read_output(&mut device).unwrap());
+
+    device.regmap.write_regs(channel.base_address() as usize, &[0x00, 0x10, 0xff, 0x0f]);
+    assert_eq!(Output::FullOn, channel.read_output(&mut device).unwrap());
+}
+
+#[test]
+fn test_ledchan_brightness_to_ticks() {
+    let channel = LedChannel::new(0).unwrap();
+
+    assert_eq!(2048, channel.brightness_to_ticks(0.5).unwrap());
+    assert_eq!(2048, channel.brightness_to_ticks(Brightness::Percent(50.0)).unwrap());
+    assert_eq!(2048, channel.brightness_to_ticks(Brightness::Level(0x8000)).unwrap());
+    assert_eq!(0, channel.brightness_to_ticks(Brightness::Level(0)).unwrap());
+    assert!(channel.brightness_to_ticks(Brightness::Percent(-1.0)).is_err());
+
+    assert_eq!(0.25, channel.ticks_to_brightness(1024));
+    assert_eq!(1.0, channel.ticks_to_brightness(4096));
+}
+
+#[test]
+fn test_ledchan_set_brightness() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    let mut channel = LedChannel::new(6).unwrap();
+
+    ctrl.set_brightness(&mut channel, Brightness::Percent(25.0)).unwrap();
+    assert_eq!(Output::Pwm { on: 0, off: 1024 }, ctrl.get_channel(&channel).unwrap().output());
+    assert_eq!(0.25, ctrl.brightness(&channel).unwrap());
+
+    // The extremes use the full on and full off bits
+    ctrl.set_brightness(&mut channel, Brightness::Level(0xffff)).unwrap();
+    assert_eq!(Output::FullOn, ctrl.get_channel(&channel).unwrap().output());
+    assert_eq!(1.0, ctrl.brightness(&channel).unwrap());
+
+    ctrl.set_brightness(&mut channel, 0.0).unwrap();
+    assert_eq!(Output::FullOff, ctrl.get_channel(&channel).unwrap().output());
+    assert_eq!(0.0, ctrl.brightness(&channel).unwrap());
+
+    assert!(ctrl.set_brightness(&mut channel, 1.01).is_err());
+}
+
+#[test]
+fn test_ledchan_brightness_curves() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+
+    // Gamma 2.0 squares the brightness, readback inverts it
+    let mut channel = LedChannel::new_with_curve(1, BrightnessCurve::gamma(2.0).unwrap()).unwrap();
+    ctrl.set_brightness(&mut channel, 0.5).unwrap();
+    assert_eq!(Output::Pwm { on: 0, off: 1024 }, ctrl.get_channel(&channel).unwrap().output());
+    assert_eq!(0.5, ctrl.brightness(&channel).unwrap());
+
+    // CIE 1931 keeps the bottom of the range dim but never jumps to zero
+    channel.set_curve(BrightnessCurve::Cie1931);
+    assert_eq!(5, channel.brightness_to_ticks(0.01).unwrap());
+    assert_eq!(4096, channel.brightness_to_ticks(1.0).unwrap());
+    ctrl.set_brightness(&mut channel, Brightness::Percent(40.0)).unwrap();
+    assert!((ctrl.brightness(&channel).unwrap() - 0.4).abs() < 0.001);
+
+    // Lookup tables interpolate between entries
+    static TABLE: [u16; 5] = [0, 64, 512, 2048, 4096];
+    channel.set_curve(BrightnessCurve::lut(&TABLE).unwrap());
+    assert_eq!(288, channel.brightness_to_ticks(0.375).unwrap());
+    assert_eq!(0.375, channel.ticks_to_brightness(288));
+    assert_eq!(1.0, channel.ticks_to_brightness(4096));
+}
+
+#[test]
+fn test_ledchan_brightness_curve_validation() {
+    assert!(BrightnessCurve::gamma(-1.0).is_err());
+    assert!(BrightnessCurve::gamma(f32::NAN).is_err());
+    assert!(BrightnessCurve::gamma(f32::INFINITY).is_err());
+
+    static SHORT: [u16; 1] = [0];
+    static DECREASING: [u16; 3] = [0, 512, 256];
+    static TOO_HIGH: [u16; 3] = [0, 2048, 4097];
+    static EMPTY: [u16; 0] = [];
+    assert!(BrightnessCurve::lut(&SHORT).is_err());
+    assert!(BrightnessCurve::lut(&DECREASING).is_err());
+    assert!(BrightnessCurve::lut(&TOO_HIGH).is_err());
+    assert!(BrightnessCurve::lut(&EMPTY).is_err());
+
+    // Flat stretches are fine
+    static FLAT: [u16; 3] = [0, 0, 4096];
+    assert!(BrightnessCurve::lut(&FLAT).is_ok());
+}
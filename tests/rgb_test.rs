@@ -0,0 +1,122 @@
+extern crate embedded_hal;
+extern crate i2cdev;
+extern crate env_logger;
+extern crate rust_pca9685;
+
+mod common;
+
+use common::MockDevice;
+
+use rust_pca9685::{
+    chain::ControllerChain,
+    channel::{
+        curve::BrightnessCurve,
+        output::Output,
+    },
+    constants,
+    controller::Controller,
+    rgb::{ ColorLed, Rgb, Rgbw, RgbLed, RgbwLed },
+    Error,
+};
+
+#[test]
+fn test_rgb_colors() {
+    assert_eq!(Rgb::from_u8(0x12, 0x34, 0x56), Rgb::from(0x123456));
+    assert_eq!(Rgb::from_u8(0xaa, 0xbb, 0xcc), "#abc".parse().unwrap());
+    assert!("#12345g".parse::<Rgb>().is_err());
+    assert!("".parse::<Rgb>().is_err());
+
+    assert_eq!(Rgb::new(1.0, 0.0, 0.0), Rgb::from_hsv(360.0, 1.0, 1.0));
+    assert_eq!(Rgb::new(0.0, 0.0, 0.5), Rgb::from_hsv(240.0, 1.0, 0.5));
+
+    // The part shared by red, green and blue moves onto the white channel
+    assert_eq!(Rgbw::new(0.5, 0.25, 0.0, 0.5), Rgbw::from(Rgb::new(1.0, 0.75, 0.5)));
+}
+
+#[test]
+fn test_rgb_led_outputs() {
+    let led = RgbLed::new(0, 1, 2).unwrap()
+        .with_white_balance(1.0, 0.5, 0.25).unwrap();
+
+    let outputs = led.outputs(Rgb::new(1.0, 1.0, 1.0)).unwrap();
+    let ticks: Vec<u16> = outputs.as_slice().iter().map(|output| output.ticks).collect();
+    assert_eq!(vec![4096, 2048, 1024], ticks);
+
+    let led = led.with_curve(BrightnessCurve::Gamma(2.0));
+    let outputs = led.outputs(Rgb::new(0.5, 0.0, 1.0)).unwrap();
+    let ticks: Vec<u16> = outputs.as_slice().iter().map(|output| output.ticks).collect();
+    assert_eq!(vec![1024, 0, 256], ticks);
+
+    // Components are checked before the white balance scales them down
+    assert!(led.outputs(Rgb::new(0.0, 1.5, 0.0)).is_err());
+    assert!(led.outputs(Rgb::new(0.0, 0.0, -0.5)).is_err());
+    assert!(RgbLed::new(0, 1, 16).is_err());
+    assert!(RgbLed::new(0, 1, 2).unwrap().with_white_balance(1.0, 1.2, 1.0).is_err());
+    assert!(RgbwLed::new(0, 1, 2, 3).unwrap().with_white_balance(1.0, 1.0, 1.0, -0.1).is_err());
+}
+
+#[test]
+fn test_rgb_led_set_color() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    let led = RgbLed::new(4, 5, 6).unwrap();
+
+    ctrl.set_color(&led, Rgb::from_hex("#ff8000").unwrap()).unwrap();
+    let channels = led.channels();
+    assert_eq!(Output::FullOn, ctrl.get_channel(&channels[0]).unwrap().output());
+    assert_eq!(Output::Pwm { on: 0, off: 2056 }, ctrl.get_channel(&channels[1]).unwrap().output());
+    assert_eq!(Output::FullOff, ctrl.get_channel(&channels[2]).unwrap().output());
+
+    // All three components go out in a single write
+    let device = ctrl.into_inner();
+    let writes = device.channel_writes();
+    assert_eq!(1, writes.len());
+    assert_eq!(constants::BASE_LED_ON_LOW + 16, writes[0][0]);
+    assert_eq!(13, writes[0].len());
+}
+
+#[test]
+fn test_rgb_led_set_color_rejects_other_boards() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    let led = RgbLed::spanning((0, 4), (0, 5), (1, 0)).unwrap();
+
+    match ctrl.set_color(&led, Rgb::new(1.0, 1.0, 1.0)) {
+        Err(Error::IndexRange(_)) => (),
+        other => panic!("expected index range error, received {:?}", other),
+    }
+    assert!(ctrl.into_inner().channel_writes().is_empty());
+}
+
+#[test]
+fn test_rgbw_led_spanning_boards() {
+    let _ = env_logger::try_init();
+
+    let mut chain = ControllerChain::new();
+    for address in &[0x40, 0x41] {
+        chain.add_board(*address, Controller::new(MockDevice::new()).unwrap()).unwrap();
+    }
+
+    let led = RgbwLed::spanning((0, 14), (0, 15), (1, 0), (1, 1)).unwrap();
+    chain.set_color(&led, Rgb::new(1.0, 1.0, 0.5)).unwrap();
+
+    assert_eq!(Output::Pwm { on: 0, off: 2048 }, chain.get_channel((0, 14)).unwrap().output());
+    assert_eq!(Output::Pwm { on: 0, off: 2048 }, chain.get_channel((0, 15)).unwrap().output());
+    assert_eq!(Output::FullOff, chain.get_channel((1, 0)).unwrap().output());
+    assert_eq!(Output::Pwm { on: 0, off: 2048 }, chain.get_channel((1, 1)).unwrap().output());
+
+    // A component on a board outside the chain fails before anything is written
+    let led = RgbLed::spanning((0, 0), (1, 2), (2, 0)).unwrap();
+    match chain.set_color(&led, Rgb::new(1.0, 1.0, 1.0)) {
+        Err(Error::IndexRange(_)) => (),
+        other => panic!("expected index range error, received {:?}", other),
+    }
+    assert_eq!(Output::Pwm { on: 0, off: 0 }, chain.get_channel((0, 0)).unwrap().output());
+
+    // Each board was written once
+    for board in chain.into_boards() {
+        assert_eq!(1, board.into_inner().into_inner().channel_writes().len());
+    }
+}
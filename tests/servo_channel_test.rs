@@ -1,12 +1,16 @@
+extern crate embedded_hal;
 extern crate i2cdev;
 #[macro_use]
 extern crate log;
 extern crate env_logger;
 extern crate rust_pca9685;
 
-use i2cdev::core::I2CDevice;
+mod common;
+
 use i2cdev::mock::MockI2CDevice;
 
+use common::MockDevice;
+
 #[allow(unused_imports)]
 use rust_pca9685::{
     constants,
@@ -23,10 +27,7 @@ use rust_pca9685::{
 #[test]
 fn test_srvchan_new_over_max() {
     let result = ServoChannel::new(16);
-    match result {
-        Ok(_) => panic!("expected error, received ok"),
-        Err(_) => return,
-    }
+    assert!(result.is_err(), "expected error, received ok");
 }
 
 #[test]
@@ -116,6 +117,56 @@ fn test_srvchan_pulse_to_degree_conversion() {
     assert!(lower_bound <= pulse && upper_bound >= pulse);
 }
 
+#[test]
+fn test_srvchan_default_settings_in_microseconds() {
+    let channel = ServoChannel::new(0).unwrap();
+
+    assert_eq!(500, channel.minimum_value());
+    assert_eq!(2500, channel.maximum_value());
+    assert_eq!(1500, channel.degrees_to_pulse_time(0.0).unwrap());
+}
+
+#[test]
+fn test_srvchan_pulse_width_to_ticks() {
+    let _ = env_logger::try_init();
+
+    let mut ctrl = Controller::new(MockDevice::new()).unwrap();
+    ctrl.set_frequency(50f32).unwrap();
+
+    // 50Hz gives a 20ms period, so 1.5ms is 7.5% of 4096 steps
+    let ticks = ctrl.microseconds_to_ticks(1500f32).unwrap();
+    assert!((ticks as i32 - 307).abs() <= 1, "expected about 307 ticks, got {}", ticks);
+
+    let pulse = ctrl.ticks_to_microseconds(ticks);
+    assert!((pulse - 1500f32).abs() < ctrl.tick_length());
+
+    // Longer than the period
+    assert!(ctrl.microseconds_to_ticks(25000f32).is_err());
+}
+
+#[test]
+fn test_srvchan_set_servo_angle_portable_across_rates() {
+    let _ = env_logger::try_init();
+
+    let mut device = MockDevice::new();
+    let mut channel = ServoChannel::new_with_settings(2, ServoSettings::new(1000, 2000)).unwrap();
+
+    for rate in &[50f32, 100.0, 200.0] {
+        let mut ctrl = Controller::new(device).unwrap();
+        ctrl.set_frequency(*rate).unwrap();
+        ctrl.set_servo_angle(&mut channel, 90.0).unwrap();
+        device = ctrl.into_inner();
+
+        let stored = channel.read_channel(&mut device).unwrap();
+        let off = (stored[2] as u16) | ((stored[3] as u16) << 8);
+
+        let ctrl = Controller::new(device).unwrap();
+        let pulse = ctrl.ticks_to_microseconds(off);
+        assert!((pulse - 2000f32).abs() <= ctrl.tick_length(), "{}Hz: expected 2000 µs, got {}", rate, pulse);
+        device = ctrl.into_inner();
+    }
+}
+
 // #[test]
 // fn test_srvchan_write_value_degrees() {
 //     let _ = env_logger::try_init();
@@ -0,0 +1,84 @@
+extern crate embedded_hal;
+extern crate i2cdev;
+extern crate env_logger;
+extern crate rust_pca9685;
+
+mod common;
+
+use std::thread;
+
+use common::MockDevice;
+
+use rust_pca9685::{
+    channel::{
+        led::LedChannel,
+        output::Output,
+        servo::ServoChannel,
+    },
+    constants,
+    controller::Controller,
+    shared::SharedController,
+};
+
+#[test]
+fn test_shared_controller_handles_across_threads() {
+    let _ = env_logger::try_init();
+
+    let shared = SharedController::new(Controller::new(MockDevice::new()).unwrap());
+    shared.lock().set_frequency(50.0).unwrap();
+
+    let servos = shared.handle();
+    let leds = servos.clone();
+
+    let pan_tilt = thread::spawn(move || {
+        let mut pan = ServoChannel::new(0).unwrap();
+        let mut tilt = ServoChannel::new(1).unwrap();
+        servos.set_servo_angle(&mut pan, 0.0).unwrap();
+        servos.set_servo_angle(&mut tilt, 90.0).unwrap();
+    });
+
+    let status = thread::spawn(move || {
+        let mut led = LedChannel::new(15).unwrap();
+        leds.set_full_on(&mut led).unwrap();
+        leds.get_channel(&led).unwrap()
+    });
+
+    pan_tilt.join().unwrap();
+    assert!(status.join().unwrap().full_on());
+
+    let ctrl = match shared.into_inner() {
+        Ok(ctrl) => ctrl,
+        Err(_) => panic!("every handle has been dropped"),
+    };
+
+    let device = ctrl.into_inner();
+    let mut written: Vec<u8> = device.channel_writes().iter()
+        .map(|write| write[0])
+        .filter(|&register| register < constants::ALL_LED_ON_LOW)
+        .collect();
+    written.sort();
+    assert_eq!(vec![0x06, 0x0a, 0x42], written);
+}
+
+#[test]
+fn test_shared_controller_into_inner_with_live_handle() {
+    let _ = env_logger::try_init();
+
+    let shared = SharedController::new(Controller::new(MockDevice::new()).unwrap());
+    let handle = shared.handle();
+
+    let shared = match shared.into_inner() {
+        Ok(_) => panic!("a handle is still alive"),
+        Err(shared) => shared,
+    };
+
+    let state = handle.with(|ctrl| {
+        let mut channel = LedChannel::new(2).unwrap();
+        ctrl.set_channel(&mut channel, (0, 0x400)).unwrap();
+        ctrl.get_channel(&channel).unwrap()
+    });
+    assert_eq!(Output::Pwm { on: 0, off: 0x400 }, state.output());
+
+    drop(handle);
+    assert!(shared.into_inner().is_ok());
+}